@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::io::{stdout, Write};
+use std::time::Duration;
+
+use crossterm::{
+	cursor,
+	event::{Event, KeyCode, poll, read},
+	ExecutableCommand, QueueableCommand,
+	Result as result,
+	style::{Color, Print, SetBackgroundColor, SetForegroundColor},
+	terminal,
+};
+use enum_iterator::IntoEnumIterator;
+
+use crate::Ingredient;
+use crate::glyphs::GlyphSet;
+use crate::resipee::Resipee;
+
+/// Browser over every ingredient and resipee in the registry.
+///
+/// Typing searches ingredient names, Up/Down select an ingredient and Enter shows the resipees
+/// that make and use it. Tab cycles the machine filter, Left/Right change the machine level
+/// filter and Esc leaves the selected ingredient, then the book
+pub struct RecipeBook<'a> {
+	resipees: Vec<&'a Resipee>,
+	glyphs: GlyphSet,
+	machines: Vec<&'static str>,
+	search: String,
+	machine: Option<usize>,
+	level: Option<u8>,
+	selected: usize,
+	focus: Option<Ingredient>,
+}
+
+impl<'a> RecipeBook<'a> {
+	pub fn new(resipees: &'a HashMap<u32, Resipee>, glyphs: GlyphSet, search: &str) -> RecipeBook<'a> {
+		let mut resipees = resipees.values().collect::<Vec<&Resipee>>();
+		resipees.sort_by_key(|r| (r.machine.name(), r.machine.min_level, format!("{:?}", r.output)));
+		let mut machines = resipees.iter().map(|r| r.machine.name()).collect::<Vec<&str>>();
+		machines.dedup();
+		RecipeBook { resipees, glyphs, machines, search: search.to_string(), machine: None, level: None, selected: 0, focus: None }
+	}
+
+	fn ingredients(&self) -> Vec<Ingredient> {
+		let search = self.search.to_lowercase();
+		Ingredient::into_enum_iter()
+			.skip(1)
+			.filter(|i| format!("{:?}", i).to_lowercase().contains(&search))
+			.collect()
+	}
+
+	/// Resipees passing the machine and level filters
+	fn filtered(&self) -> Vec<&'a Resipee> {
+		self.resipees.iter()
+			.filter(|r| match self.machine {
+				Some(m) => r.machine.name() == self.machines[m],
+				None => true
+			})
+			.filter(|r| match self.level {
+				Some(l) => r.machine.min_level < l,
+				None => true
+			})
+			.copied()
+			.collect()
+	}
+
+	fn lines(&self) -> Vec<(Color, String)> {
+		let mut lines = vec![];
+		match self.focus {
+			Some(ing) => {
+				let resipees = self.filtered();
+				lines.push((ing.get_colour(), format!("{} {:?}", self.glyphs.ingredient(ing), ing)));
+				lines.push((Color::Reset, String::from("Made by:")));
+				let made = resipees.iter().filter(|r| r.output == ing).map(|r| format!("  {:?}", r)).collect::<Vec<String>>();
+				if made.is_empty() {
+					lines.push((Color::Reset, String::from("  Base ingredient. Comes from input only")));
+				}
+				lines.extend(made.into_iter().map(|l| (Color::Reset, l)));
+				let byproduct = resipees.iter().filter(|r| r.byproducts.contains(&ing)).map(|r| format!("  {:?}", r)).collect::<Vec<String>>();
+				if !byproduct.is_empty() {
+					lines.push((Color::Reset, String::from("Byproduct of:")));
+					lines.extend(byproduct.into_iter().map(|l| (Color::Reset, l)));
+				}
+				lines.push((Color::Reset, String::from("Used by:")));
+				let used = resipees.iter().filter(|r| r.input.contains(&ing)).map(|r| format!("  {:?}", r)).collect::<Vec<String>>();
+				if used.is_empty() {
+					lines.push((Color::Reset, String::from("  Nothing")));
+				}
+				lines.extend(used.into_iter().map(|l| (Color::Reset, l)));
+			}
+			None => {
+				lines.push((Color::Reset, String::from("Ingredients:")));
+				for ing in self.ingredients() {
+					lines.push((ing.get_colour(), format!("  {} {:?}", self.glyphs.ingredient(ing), ing)));
+				}
+				lines.push((Color::Reset, String::from("Resipees:")));
+				for r in self.filtered() {
+					lines.push((r.output.get_colour(), format!("  {:?}", r)));
+				}
+			}
+		}
+		lines
+	}
+
+	fn print(&self) -> result<()> {
+		let mut stdout = stdout();
+		stdout.execute(terminal::Clear(terminal::ClearType::All))?
+			.queue(cursor::MoveTo(0, 0))?
+			.queue(SetForegroundColor(Color::Reset))?
+			.queue(Print(format!("Recipe book | search: '{}' | machine: {} | level: {} | Tab machine, Left/Right level, Enter select, Esc back",
+				self.search,
+				match self.machine {
+					Some(m) => self.machines[m],
+					None => "any"
+				},
+				match self.level {
+					Some(l) => format!("{}", l),
+					None => String::from("any")
+				})))?;
+		let (_, rows) = terminal::size()?;
+		for (i, (colour, line)) in self.lines().iter().take(rows.max(2) as usize - 1).enumerate() {
+			// Ingredient lines start after the "Ingredients:" heading
+			let highlight = self.focus.is_none() && i == self.selected + 1;
+			stdout.queue(cursor::MoveToNextLine(1))?
+				.queue(SetBackgroundColor(if highlight { Color::DarkGrey } else { Color::Reset }))?
+				.queue(SetForegroundColor(*colour))?
+				.queue(Print(line))?
+				.queue(SetBackgroundColor(Color::Reset))?;
+		}
+		stdout.queue(SetForegroundColor(Color::Reset))?;
+		stdout.flush()?;
+		Ok(())
+	}
+
+	pub fn view(&mut self) -> result<()> {
+		loop {
+			self.print()?;
+			loop {
+				if poll(Duration::from_millis(500))? {
+					if let Event::Key(key) = read()? {
+						let count = self.ingredients().len();
+						match key.code {
+							KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+							KeyCode::Down => if self.selected + 1 < count {
+								self.selected += 1
+							}
+							KeyCode::Enter => self.focus = self.ingredients().get(self.selected).copied(),
+							KeyCode::Tab => self.machine = match self.machine {
+								Some(m) if m + 1 < self.machines.len() => Some(m + 1),
+								Some(_) => None,
+								None if self.machines.is_empty() => None,
+								None => Some(0)
+							},
+							KeyCode::Right => self.level = Some(self.level.map_or(1, |l| l.saturating_add(1))),
+							KeyCode::Left => self.level = match self.level {
+								Some(l) if l > 1 => Some(l - 1),
+								_ => None
+							},
+							KeyCode::Char(c) if self.focus.is_none() => {
+								self.search.push(c);
+								self.selected = 0;
+							}
+							KeyCode::Backspace if self.focus.is_none() => {
+								self.search.pop();
+								self.selected = 0;
+							}
+							KeyCode::Esc => match self.focus {
+								Some(_) => self.focus = None,
+								None => return Ok(())
+							}
+							_ => continue
+						}
+						break;
+					}
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,168 @@
+use std::fs::File;
+use std::io::{stdout, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crossterm::{
+	cursor,
+	event::{Event, KeyCode, poll, read},
+	ExecutableCommand, QueueableCommand,
+	Result as result,
+	style::{Color, Print, SetForegroundColor},
+	terminal,
+};
+
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug)]
+pub enum Level {
+	Info,
+	Warn,
+	Error,
+}
+
+impl Level {
+	pub fn get_colour(&self) -> Color {
+		match self {
+			Level::Info => Color::Reset,
+			Level::Warn => Color::Yellow,
+			Level::Error => Color::Red,
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Level::Info => "INFO",
+			Level::Warn => "WARN",
+			Level::Error => "ERROR",
+		}
+	}
+
+	pub fn parse(s: &str) -> Option<Level> {
+		match s {
+			"i" | "info" => Some(Level::Info),
+			"w" | "warn" => Some(Level::Warn),
+			"e" | "error" => Some(Level::Error),
+			_ => None
+		}
+	}
+
+	fn next(&self) -> Level {
+		match self {
+			Level::Info => Level::Warn,
+			Level::Warn => Level::Error,
+			Level::Error => Level::Info,
+		}
+	}
+}
+
+pub struct Entry {
+	pub level: Level,
+	pub message: String,
+	pub time: Duration,
+}
+
+impl Entry {
+	fn line(&self) -> String {
+		format!("[{:>9.3}] {:<5} {}", self.time.as_secs_f32(), self.level.name(), self.message)
+	}
+}
+
+/// Unbounded scrollback of every message shown in the console, optionally mirrored to a session log file
+pub struct Console {
+	entries: Vec<Entry>,
+	start: Instant,
+	log: Option<File>,
+}
+
+impl Console {
+	pub fn new() -> Console {
+		Console { entries: vec![], start: Instant::now(), log: None }
+	}
+
+	/// Mirror every entry to `path`. Entry times are seconds since the session start written in the header
+	pub fn open_log(&mut self, path: &str) -> std::io::Result<()> {
+		let mut file = File::create(path)?;
+		writeln!(file, "# session started at {} (unix time)", SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs())?;
+		for e in &self.entries {
+			writeln!(file, "{}", e.line())?;
+		}
+		self.log = Some(file);
+		Ok(())
+	}
+
+	pub fn push(&mut self, level: Level, message: String) {
+		let entry = Entry { level, message, time: self.start.elapsed() };
+		if let Some(file) = &mut self.log {
+			// A failing log file shouldn't take the game down with it
+			if writeln!(file, "{}", entry.line()).is_err() {
+				self.log = None;
+			}
+		}
+		self.entries.push(entry);
+	}
+
+	/// The `n` most recent entries, newest first
+	pub fn latest(&self, n: usize) -> impl Iterator<Item = &Entry> {
+		self.entries.iter().rev().take(n)
+	}
+
+	fn filtered(&self, min_level: Level, filter: &str) -> Vec<&Entry> {
+		let filter = filter.to_lowercase();
+		self.entries.iter()
+			.filter(|e| e.level >= min_level && e.message.to_lowercase().contains(&filter))
+			.collect()
+	}
+
+	/// Full screen pager over the scrollback. Up/Down scroll, PageUp/PageDown page, Home/End jump,
+	/// Tab cycles the minimum level and Esc closes
+	pub fn view(&self, min_level: Level, filter: &str) -> result<()> {
+		let mut stdout = stdout();
+		let mut min_level = min_level;
+		let mut entries = self.filtered(min_level, filter);
+		let (_, rows) = terminal::size()?;
+		let page = (rows.max(3) - 2) as usize;
+		// Offset of the bottom line from the newest entry, so the view starts at the end
+		let mut offset = 0usize;
+		loop {
+			let max_offset = entries.len().saturating_sub(page);
+			offset = offset.min(max_offset);
+			let end = entries.len() - offset;
+			let start = end.saturating_sub(page);
+			stdout.execute(terminal::Clear(terminal::ClearType::All))?
+				.queue(cursor::MoveTo(0, 0))?
+				.queue(SetForegroundColor(Color::Reset))?
+				.queue(Print(format!("Log: {}+{} | {}-{} of {} | Up/Down PgUp/PgDn Home/End, Tab level, Esc close",
+					min_level.name(),
+					if filter.is_empty() { String::new() } else { format!(" matching '{}'", filter) },
+					if entries.is_empty() { 0 } else { start + 1 }, end, entries.len())))?;
+			for e in &entries[start..end] {
+				stdout.queue(cursor::MoveToNextLine(1))?
+					.queue(SetForegroundColor(e.level.get_colour()))?
+					.queue(Print(e.line()))?;
+			}
+			stdout.queue(SetForegroundColor(Color::Reset))?;
+			stdout.flush()?;
+
+			loop {
+				if poll(Duration::from_millis(500))? {
+					if let Event::Key(key) = read()? {
+						match key.code {
+							KeyCode::Up => offset = (offset + 1).min(max_offset),
+							KeyCode::Down => offset = offset.saturating_sub(1),
+							KeyCode::PageUp => offset = (offset + page).min(max_offset),
+							KeyCode::PageDown => offset = offset.saturating_sub(page),
+							KeyCode::Home => offset = max_offset,
+							KeyCode::End => offset = 0,
+							KeyCode::Tab => {
+								min_level = min_level.next();
+								entries = self.filtered(min_level, filter);
+								offset = 0;
+							}
+							KeyCode::Esc => return Ok(()),
+							_ => continue
+						}
+						break;
+					}
+				}
+			}
+		}
+	}
+}
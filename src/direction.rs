@@ -0,0 +1,78 @@
+use enum_iterator::IntoEnumIterator;
+
+/// A cell on the grid as `(x, y)`, with `(0, 0)` at the top left
+pub type Position = (usize, usize);
+
+/// A side of a cell, used for where nodes take their inputs from and send their outputs to
+#[derive(Copy, Clone, PartialEq, Eq, Debug, IntoEnumIterator)]
+pub enum Direction {
+	Up,
+	Right,
+	Down,
+	Left,
+}
+
+impl Direction {
+	pub fn opposite(&self) -> Direction {
+		match self {
+			Direction::Up => Direction::Down,
+			Direction::Right => Direction::Left,
+			Direction::Down => Direction::Up,
+			Direction::Left => Direction::Right,
+		}
+	}
+
+	pub fn clockwise(&self) -> Direction {
+		match self {
+			Direction::Up => Direction::Right,
+			Direction::Right => Direction::Down,
+			Direction::Down => Direction::Left,
+			Direction::Left => Direction::Up,
+		}
+	}
+
+	/// Where this side ends up when a node laid out facing right is turned to face `facing`
+	pub fn turn(&self, facing: Direction) -> Direction {
+		let (mut side, mut f) = (*self, Direction::Right);
+		while f != facing {
+			side = side.clockwise();
+			f = f.clockwise();
+		}
+		side
+	}
+
+	/// Arrow pointing this way
+	pub fn arrow(&self) -> char {
+		match self {
+			Direction::Up => '↑',
+			Direction::Right => '→',
+			Direction::Down => '↓',
+			Direction::Left => '←',
+		}
+	}
+
+	/// The neighbour of `pos` on this side, if it's inside a `width` by `height` grid
+	pub fn step(&self, pos: Position, width: usize, height: usize) -> Option<Position> {
+		let (x, y) = pos;
+		match self {
+			Direction::Up => y.checked_sub(1).map(|y| (x, y)),
+			Direction::Right => Some((x + 1, y)).filter(|_| x + 1 < width),
+			Direction::Down => Some((x, y + 1)).filter(|_| y + 1 < height),
+			Direction::Left => x.checked_sub(1).map(|x| (x, y)),
+		}
+	}
+
+	/// Letter used for this direction in commands, such as the 'l' and 'r' in ':p;p(lr)'
+	pub fn letter(&self) -> char {
+		match self {
+			Direction::Up => 'u',
+			Direction::Right => 'r',
+			Direction::Down => 'd',
+			Direction::Left => 'l',
+		}
+	}
+
+	pub fn from_letter(c: char) -> Option<Direction> {
+		Direction::into_enum_iter().find(|d| d.letter() == c)
+	}
+}
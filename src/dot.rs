@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crossterm::style::Color;
+use enum_iterator::IntoEnumIterator;
+
+use crate::{Ingredient, Node};
+use crate::grid::Grid;
+use crate::resipee::Resipee;
+
+/// Graphviz colour for an ingredient, or `None` where it has no colour of its own
+fn colour(ing: Ingredient) -> Option<String> {
+	match ing.get_colour() {
+		Color::Rgb { r, g, b } => Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+		_ => None
+	}
+}
+
+fn ingredient_node(out: &mut String, ing: Ingredient) {
+	match colour(ing) {
+		Some(c) => writeln!(out, "\t\"{:?}\" [shape=ellipse, style=filled, fillcolor=\"{}\"];", ing, c),
+		None => writeln!(out, "\t\"{:?}\" [shape=ellipse];", ing)
+	}.unwrap();
+}
+
+/// The resipee registry as a bipartite graph of ingredients and the machine resipees between them.
+/// Byproducts are dashed and catalysts dotted
+pub fn resipees_to_dot(resipees: &HashMap<u32, Resipee>) -> String {
+	let mut out = String::from("digraph resipees {\n\trankdir=LR;\n");
+	for ing in Ingredient::into_enum_iter().skip(1) {
+		ingredient_node(&mut out, ing);
+	}
+	let mut resipees = resipees.values().collect::<Vec<&Resipee>>();
+	resipees.sort_by_key(|r| (r.machine.name(), r.machine.min_level, format!("{:?}", r.output)));
+	for (n, r) in resipees.iter().enumerate() {
+		writeln!(out, "\t\"r{}\" [shape=box, label=\"{}\\nmin level {}\"];", n, r.machine.name(), r.machine.min_level + 1).unwrap();
+		for input in r.consumed() {
+			writeln!(out, "\t\"{:?}\" -> \"r{}\";", input, n).unwrap();
+		}
+		for catalyst in r.catalysts {
+			writeln!(out, "\t\"{:?}\" -> \"r{}\" [style=dotted, label=\"catalyst\"];", catalyst, n).unwrap();
+		}
+		writeln!(out, "\t\"r{}\" -> \"{:?}\";", n, r.output).unwrap();
+		for byproduct in r.byproducts {
+			writeln!(out, "\t\"r{}\" -> \"{:?}\" [style=dashed];", n, byproduct).unwrap();
+		}
+	}
+	out.push_str("}\n");
+	out
+}
+
+/// The grid as a flow graph with an edge for every connection between neighbouring nodes,
+/// labelled with the ingredient it carries. Power connections are dashed
+pub fn grid_to_dot(grid: &Grid) -> String {
+	let mut out = String::from("digraph factory {\n\trankdir=LR;\n");
+	// The back of a machine is drawn as part of the machine
+	for (x, y, node) in grid.nodes().filter(|(_, _, n)| !matches!(n, Node::Back(_, _))) {
+		match colour(node.get_ingredient(grid.resipees().clone())) {
+			Some(c) => writeln!(out, "\t\"{}_{}\" [shape=box, label=\"{}\\n({}, {})\", color=\"{}\"];", x, y, node.name(), x, y, c),
+			None => writeln!(out, "\t\"{}_{}\" [shape=box, label=\"{}\\n({}, {})\"];", x, y, node.name(), x, y)
+		}.unwrap();
+	}
+	for (x, y, _) in grid.nodes() {
+		for (sx, sy) in grid.sources(x, y) {
+			let ing = grid.flow((sx, sy), (x, y));
+			match colour(ing) {
+				Some(c) => writeln!(out, "\t\"{}_{}\" -> \"{}_{}\" [label=\"{:?}\", color=\"{}\"];", sx, sy, x, y, ing, c),
+				None => writeln!(out, "\t\"{}_{}\" -> \"{}_{}\" [label=\"{:?}\"];", sx, sy, x, y, ing)
+			}.unwrap();
+		}
+		if let Some((px, py)) = grid.powered_by(x, y) {
+			writeln!(out, "\t\"{}_{}\" -> \"{}_{}\" [style=dashed, label=\"power\"];", px, py, x, y).unwrap();
+		}
+	}
+	out.push_str("}\n");
+	out
+}
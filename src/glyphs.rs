@@ -0,0 +1,97 @@
+use crate::{Ingredient, machines, Node};
+use crate::direction::Direction;
+
+/// How nodes and ingredients are drawn. Every glyph takes up two columns, the width of one cell
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GlyphSet {
+	/// The custom font merged in at `0x1113`. Anything the font has no glyph for is drawn as in `Box`
+	Font,
+	/// Box drawing and plain characters that any terminal font has
+	Box,
+}
+
+impl GlyphSet {
+	pub fn parse(s: &str) -> Option<GlyphSet> {
+		match s {
+			"font" => Some(GlyphSet::Font),
+			"box" => Some(GlyphSet::Box),
+			_ => None
+		}
+	}
+
+	pub fn empty(&self) -> String {
+		match self {
+			GlyphSet::Font => String::from('ᄢ'),
+			GlyphSet::Box => String::from("· ")
+		}
+	}
+
+	pub fn node(&self, node: &Node) -> String {
+		match (self, node.char()) {
+			(GlyphSet::Font, Some(c)) => String::from(c),
+			_ => match node {
+				Node::In(_) => String::from("I>"),
+				Node::Out(_, _, _) => String::from(">O"),
+				Node::PowerRight => String::from("P<"),
+				Node::PowerLeft => String::from(">P"),
+				Node::Generator => String::from("G+"),
+				Node::PowerLine => String::from("╬═"),
+				// Machines show the way their output goes, the level is in their info
+				Node::Machine(_) => match node.spec() {
+					Some(spec) => format!("{}{}", spec.letter, node.outputs().first().map_or(' ', |d| d.arrow())),
+					None => String::from("??")
+				}
+				Node::Back(name, _) => match machines::spec(name) {
+					Some(spec) => format!("{}=", spec.letter.to_ascii_lowercase()),
+					None => String::from("??")
+				}
+				Node::Split(_, _, f) => format!("S{}", f.arrow()),
+				Node::Merge(_, _, f) => format!("M{}", f.arrow()),
+				// Pipes and crossings show the way they flow after their shape
+				Node::Pipe(_, from, to) => format!("{}{}", pipe(&[*from, *to]), to.arrow()),
+				Node::Tee(_, from) => {
+					let sides = [*from, from.clockwise(), from.clockwise().opposite()];
+					format!("{}{}", pipe(&sides), if sides.contains(&Direction::Right) { '─' } else { ' ' })
+				}
+				Node::Cross(_, h, _, v) => format!("┼{}", diagonal(*h, *v)),
+				Node::Filter(_, _, f) => format!("F{}", f.arrow()),
+			}
+		}
+	}
+
+	pub fn ingredient(&self, ing: Ingredient) -> String {
+		match self {
+			GlyphSet::Font => String::from(ing.char()),
+			GlyphSet::Box => String::from(if ing == Ingredient::None { "  " } else { "██" })
+		}
+	}
+}
+
+/// Box drawing for a piece of pipe open on `sides`, for the first column of its cell
+fn pipe(sides: &[Direction]) -> char {
+	let open = |d| sides.contains(&d);
+	match (open(Direction::Up), open(Direction::Right), open(Direction::Down), open(Direction::Left)) {
+		(false, true, false, true) => '─',
+		(true, false, true, false) => '│',
+		(true, false, false, true) => '┘',
+		(false, false, true, true) => '┐',
+		(true, true, false, false) => '└',
+		(false, true, true, false) => '┌',
+		(true, false, true, true) => '┤',
+		(true, true, true, false) => '├',
+		(true, true, false, true) => '┴',
+		(false, true, true, true) => '┬',
+		_ => '┼'
+	}
+}
+
+/// Arrow pointing both the way `h` goes and the way `v` goes, for a crossing's two flows
+fn diagonal(h: Direction, v: Direction) -> char {
+	match (h, v) {
+		(Direction::Right, Direction::Up) => '↗',
+		(Direction::Right, Direction::Down) => '↘',
+		(Direction::Left, Direction::Down) => '↙',
+		(Direction::Left, Direction::Up) => '↖',
+		_ => '─'
+	}
+}
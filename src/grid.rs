@@ -1,5 +1,5 @@
 use std::{io::{stdout, Write}};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::process::exit;
 use std::time::Duration;
 
@@ -14,257 +14,583 @@ use enum_iterator::IntoEnumIterator;
 use regex::Regex;
 
 use crate::{Ingredient, Node};
-use crate::resipee::Resipee;
+use crate::book::RecipeBook;
+use crate::{dot, lint, machines, power, validate};
+use crate::console::{Console, Level};
+use crate::direction::{Direction, Position};
+use crate::machines::{Machine, MAX_OUTPUTS};
+use crate::glyphs::GlyphSet;
+use crate::resipee::{self, Chain, Resipee, Stack};
 
-const SPACE: char = 'ᄢ';
+/// How many times a second the factory moves on
+pub const TICKS_PER_SECOND: u32 = 4;
+/// Items one cell of line holds at once, for each way through it
+pub const LINE_CAPACITY: usize = 2;
+/// Ticks an item takes to travel along one cell of line
+pub const LINE_TICKS: u32 = 2;
+
+/// An item travelling along a line
+#[derive(Copy, Clone)]
+struct Item {
+	ingredient: Ingredient,
+	at: Position,
+	/// The cell it came into `at` from
+	from: Position,
+	/// Ticks before it can move on. Stays at 0 while the way on is blocked
+	wait: u32,
+}
+
+/// Where an item handed on from a cell went
+enum Hop {
+	/// An output or machine took it
+	Taken,
+	/// Into the line at this cell
+	Along(Position),
+	/// Nowhere, as nothing on the way on had room for it
+	Blocked,
+}
 
 pub struct Grid {
 	grid: Vec<Vec<Option<Node>>>,
 	width: usize,
 	height: usize,
-	console: [Option<String>; 3],
-	console_is_err: [bool; 3],
+	pub console: Console,
 	resipees: HashMap<u32, Resipee>,
+	cursor: (usize, usize),
+	rulers: bool,
+	/// Cells flagged by the last `:lint`, cleared when the grid changes
+	lint: Vec<(usize, usize, String)>,
+	/// Cells picked out by the last `:trace`, with the rest of the grid dimmed. Empty when not tracing
+	trace: Vec<(usize, usize)>,
+	glyphs: GlyphSet,
+	/// Ticks since the game started
+	tick: u64,
+	/// Items on their way along lines, oldest first
+	items: Vec<Item>,
+	/// How many items each cell has handed on, so the next takes the following branch
+	turns: HashMap<Position, usize>,
+	/// Line cells that have carried something other than what they're meant to, along with what.
+	/// Cleared when the grid changes
+	mixed: Vec<(usize, usize, Ingredient)>,
 }
 
 impl Grid {
 	pub fn new(width: usize, height: usize, resipees: HashMap<u32, Resipee>) -> Grid {
-		Grid { grid: vec![vec![None; width + 3]; height + 4], width: width - 1, height: height - 1, console: [None, None, None], console_is_err: [false, false, false], resipees }
+		Grid { grid: vec![vec![None; width + 3]; height + 4], width: width - 1, height: height - 1, console: Console::new(), resipees, cursor: (0, 0), rulers: true, lint: vec![], trace: vec![], glyphs: GlyphSet::Font, tick: 0,
+			items: vec![], turns: HashMap::new(), mixed: vec![] }
 	}
 	
-	pub fn set_node(&mut self, x: usize, y: usize, node: Node) -> Result<(), ()> {
-		if y >= self.height || x >= self.grid.first().unwrap().len() {
-			return Err(());
+	/// Puts `node` at `(x, y)`, replacing whatever's there. Machines two cells long also take the
+	/// cell behind them, which has to be free. Replacing either cell of one removes all of it
+	pub fn set_node(&mut self, x: usize, y: usize, node: Node) -> Result<(), String> {
+		if y >= self.height || x >= self.width {
+			return Err(format!("Cannot place a node at ({}, {}). Outside of the grid", x, y));
+		}
+		let back = match node {
+			Node::Machine(m) if !node.back_inputs().is_empty() => match m.facing.opposite().step((x, y), self.width, self.height) {
+				Some(b) if self.get_node_at_pos(b.0, b.1).is_none() || self.partner(x, y) == Some(b) => Some(b),
+				Some(b) => return Err(format!("{} is two cells long and ({}, {}) behind it is taken", m.name, b.0, b.1)),
+				None => return Err(format!("{} is two cells long and there's no room behind it at ({}, {})", m.name, x, y))
+			}
+			_ => None
+		};
+		let mut changed = vec![(x, y)];
+		if let Some((px, py)) = self.partner(x, y) {
+			self.grid[py][px] = None;
+			changed.push((px, py));
 		}
 		self.grid[y][x] = Some(node);
-		self.update(x, y);
+		if let (Node::Machine(m), Some((bx, by))) = (node, back) {
+			self.grid[by][bx] = Some(Node::Back(m.name, m.facing));
+			changed.push((bx, by));
+		}
+		changed.dedup();
+		for (cx, cy) in changed {
+			self.refresh_around(cx, cy);
+		}
 		Ok(())
 	}
 	
-	fn update(&mut self, x: usize, y: usize) {
-		let node_at_pos = match self.get_node_at_pos(x, y) {
-			Some(n) => *n,
-			None => return
-		};
-		match node_at_pos {
-			// Terminal nodes
-			Node::Out(_) => {
-				self.grid[y][x] = Some(if x != 0 {
-					match self.get_node_at_pos(x - 1, y) {
-						Some(n) => Node::Out(n.get_ingredient(self.resipees.clone())),
-						None => Node::Out(Ingredient::None)
-					}
-				} else {
-					Node::Out(Ingredient::None)
-				})
-			}
-			Node::PowerRight => {
-				if x != 0 {
-					self.grid[y][x] = match self.get_node_at_pos(x - 1, y) {
-						Some(n) => match *n {
-							Node::Split(i, _) => Some(Node::Split(i, true)),
-							_ => Some(*n)
-						}
-						None => Some(node_at_pos)
-					}
-				}
+	/// Clears the cell at `(x, y)`, along with the rest of the machine when it's part of one two cells long
+	pub fn remove_node(&mut self, x: usize, y: usize) -> Option<Node> {
+		if let Some((px, py)) = self.partner(x, y) {
+			self.grid[py][px] = None;
+			self.refresh_around(px, py);
+		}
+		let previous = self.grid[y][x].take();
+		self.refresh_around(x, y);
+		previous
+	}
+	
+	/// Re-propagates everything that could have changed after the cell at `(x, y)` did
+	fn refresh_around(&mut self, x: usize, y: usize) {
+		self.lint.clear();
+		self.trace.clear();
+		self.mixed.clear();
+		let grid = &self.grid;
+		self.items.retain(|i| matches!(grid[i.at.1][i.at.0], Some(n) if n.carries()));
+		let mut dirty = self.neighbours(x, y);
+		dirty.extend(self.corners(x, y));
+		dirty.push((x, y));
+		// A machine two cells long is what changes when something next to its back does
+		let dirty = dirty.into_iter().map(|(dx, dy)| self.owner(dx, dy)).collect();
+		self.propagate(dirty);
+	}
+	
+	/// Recomputes every node downstream of the `dirty` cells.
+	///
+	/// Each node is worked out once, after everything feeding it, so long chains don't recurse.
+	/// Nodes that feed back into themselves can't be put in that order, so they're reported as a
+	/// feedback loop and settled by going round the loop a bounded number of times
+	pub fn propagate(&mut self, dirty: Vec<(usize, usize)>) {
+		let mut region: Vec<(usize, usize)> = vec![];
+		for (x, y) in dirty {
+			if self.get_node_at_pos(x, y).is_none() {
+				continue;
 			}
-			Node::PowerLeft => {
-				if x != self.width {
-					self.grid[y][x] = match self.get_node_at_pos(x + 1, y) {
-						Some(n) => match *n {
-							Node::Merge(i, _) => Some(Node::Merge(i, true)),
-							_ => Some(*n)
-						}
-						None => Some(node_at_pos)
-					}
+			for pos in self.trace_from(x, y, true) {
+				if !region.contains(&pos) {
+					region.push(pos);
 				}
 			}
-			// Non-terminal nodes
-			Node::Pipe(_, t) => {
-				if t == 0 || t == 1 || t == 2 {
-					if x != 0 {
-						self.grid[y][x] = Some(Node::Pipe(match self.get_node_at_pos(x - 1, y) {
-							Some(n) => match *n {
-								Node::Merge(_, _) | Node::Comb1(_, _, _, _) | Node::Comb2(_, _, _, _) | Node::In(_) => n.get_ingredient(self.resipees.clone()),
-								Node::Pipe(i, t) => {
-									if t == 0 || t == 3 || t == 4 {
-										i
-									} else {
-										Ingredient::None
-									}
-								}
-								_ => Ingredient::None
-							}
-							None => Ingredient::None
-						}, t))
-					}
-				} else if t == 3 {
-					if y != self.height {
-						self.grid[y][x] = Some(Node::Pipe(match self.get_node_at_pos(x, y + 1) {
-							Some(n) => {
-								match *n {
-									Node::Split(i, _) => i,
-									_ => Ingredient::None
-								}
-							}
-							None => Ingredient::None
-						}, 3))
-					}
-				} else {
-					if y != 0 {
-						self.grid[y][x] = Some(Node::Pipe(match self.get_node_at_pos(x, y - 1) {
-							Some(n) => {
-								match *n {
-									Node::Split(i, _) => i,
-									_ => Ingredient::None
-								}
-							}
-							None => Ingredient::None
-						}, 4))
+		}
+		// Indices into `region` of each node's inputs from inside the region
+		let inputs = region.iter().map(|(x, y)| {
+			self.sources(*x, *y).into_iter().chain(self.powered_by(*x, *y))
+				.filter_map(|pos| region.iter().position(|r| *r == pos))
+				.collect::<Vec<usize>>()
+		}).collect::<Vec<Vec<usize>>>();
+		let mut waiting = inputs.iter().map(|i| i.len()).collect::<Vec<usize>>();
+		let mut ready = (0..region.len()).filter(|i| waiting[*i] == 0).collect::<VecDeque<usize>>();
+		let mut done = vec![false; region.len()];
+		while let Some(i) = ready.pop_front() {
+			self.recompute(region[i].0, region[i].1);
+			done[i] = true;
+			for (j, ins) in inputs.iter().enumerate() {
+				if !done[j] && ins.contains(&i) {
+					waiting[j] -= 1;
+					if waiting[j] == 0 {
+						ready.push_back(j);
 					}
 				}
-				if t == 0 || t == 3 || t == 4 {
-					if x != self.width {
-						self.update(x + 1, y);
-					}
-				} else if t == 1 {
-					if y != 0 {
-						self.update(x, y - 1);
+			}
+		}
+		
+		let stuck = region.iter().zip(done).filter(|(_, d)| !d).map(|(pos, _)| *pos).collect::<Vec<(usize, usize)>>();
+		if stuck.is_empty() {
+			return;
+		}
+		// Nodes only downstream of a loop are stuck too, so pick out the ones that lead back to themselves
+		let looped = stuck.iter()
+			.filter(|(x, y)| self.targets(*x, *y).iter().any(|(tx, ty)| self.trace_from(*tx, *ty, true).contains(&(*x, *y))))
+			.map(|(x, y)| format!("({}, {})", x, y))
+			.collect::<Vec<String>>();
+		self.new_warn(format!("Feedback loop through {}", looped.join(", ")));
+		for _ in 0..stuck.len() {
+			for (x, y) in &stuck {
+				self.recompute(*x, *y);
+			}
+		}
+	}
+	
+	/// Sets the node at `(x, y)` from what its neighbours are feeding it
+	fn recompute(&mut self, x: usize, y: usize) {
+		let node = match self.get_node_at_pos(x, y) {
+			Some(n) => *n,
+			None => return
+		};
+		let ings = self.reads(x, y).iter().map(|read| match read {
+			Some(from) => self.flow(*from, (x, y)),
+			None => Ingredient::None
+		}).collect::<Vec<Ingredient>>();
+		let powered = power::network_at(self, x, y).is_some_and(|n| n.supplied());
+		self.grid[y][x] = Some(match node {
+			// Counting starts again whenever something else starts arriving. Outputs that aren't
+			// meant to receive anything in particular, such as one past a filter, keep counting what
+			// they took up for as long as an input upstream could still be sending it
+			Node::Out(i, n, since) if i == ings[0] || (ings[0] == Ingredient::None && self.trace_from(x, y, false).iter()
+				.any(|(ux, uy)| matches!(self.get_node_at_pos(*ux, *uy), Some(Node::In(u)) if *u == i))) => Node::Out(i, n, since),
+			Node::Out(_, _, _) => Node::Out(ings[0], 0, self.tick),
+			Node::Pipe(_, from, to) => Node::Pipe(ings[0], from, to),
+			Node::Tee(_, from) => Node::Tee(ings[0], from),
+			Node::Cross(_, h, _, v) => Node::Cross(ings[0], h, ings[1], v),
+			Node::Filter(_, pass, f) => Node::Filter(ings[0], pass, f),
+			// Whatever it was holding goes when it's fed something else
+			Node::Machine(m) => {
+				let mut inputs = m.inputs;
+				for (n, input) in inputs.iter_mut().enumerate() {
+					let ing = ings.get(n).copied().unwrap_or(Ingredient::None);
+					if input.ingredient != ing {
+						*input = Stack { ingredient: ing, count: 0 };
 					}
-				} else if y != self.height {
-					self.update(x, y + 1);
 				}
-			}
-			Node::Comb1(_, _, _, l) | Node::Comb2(_, _, _, l) => {
-				let node_type = match node_at_pos {
-					Node::Comb1(_, _, _, _) => Node::Comb1,
-					_ => Node::Comb2
+				let (progress, output) = if inputs.map(|s| s.ingredient) == m.fed() {
+					(m.progress, m.output)
+				} else {
+					(0, [Stack::empty(); MAX_OUTPUTS])
 				};
-				let mut top = Ingredient::None;
-				let mut left = Ingredient::None;
-				let mut bottom = Ingredient::None;
-				if y != 0 {
-					top = match self.get_node_at_pos(x, y - 1) {
-						Some(n) => match *n {
-							Node::Split(i, _) => i,
-							Node::Pipe(i, t) => {
-								if t == 2 { i } else { Ingredient::None }
-							}
-							_ => Ingredient::None
-						}
-						None => Ingredient::None
+				Node::Machine(Machine { inputs, output, powered, progress, ..m })
+			}
+			Node::Split(_, _, f) => Node::Split(ings[0], powered, f),
+			// Merge only carries what comes from below when there's nothing above
+			Node::Merge(_, _, f) => Node::Merge(ings.iter().copied().find(|i| *i != Ingredient::None).unwrap_or(Ingredient::None), powered, f),
+			n => n
+		});
+	}
+	
+	/// Moves the factory on a tick. Inputs send out an item a second and machines work towards
+	/// their resipees, sending what they make down the line when they finish
+	pub fn tick(&mut self) {
+		self.tick += 1;
+		self.move_items();
+		for y in 0..self.height {
+			for x in 0..self.width {
+				match self.grid[y][x] {
+					Some(Node::In(i)) if i != Ingredient::None && self.tick.is_multiple_of(TICKS_PER_SECOND as u64) => {
+						self.send((x, y), i);
 					}
-				}
-				if x != 0 {
-					left = match self.get_node_at_pos(x - 1, y) {
-						Some(n) => match *n {
-							Node::In(_) | Node::Comb1(_, _, _, _) | Node::Comb2(_, _, _, _) | Node::Merge(_, _) => n.get_ingredient(self.resipees.clone()),
-							Node::Pipe(i, t) => {
-								if t == 0 || t == 3 || t == 4 {
-									i
-								} else {
-									Ingredient::None
+					Some(node @ Node::Machine(mut m)) => {
+						if let Some(r) = node.making(&self.resipees) {
+							// Each lot starts by taking what it needs from the inputs, which can then refill
+							// while it's being made
+							if m.progress == 0 && m.stocked() && m.room_for(&r) {
+								for input in m.inputs.iter_mut().filter(|s| s.ingredient != Ingredient::None && !r.catalysts.contains(&s.ingredient)) {
+									input.count -= 1;
 								}
+								m.progress = node.speed();
+							} else if m.progress > 0 {
+								m.progress += node.speed();
 							}
-							_ => Ingredient::None
-						}
-						None => Ingredient::None
-					}
-				}
-				if y != self.height {
-					bottom = match self.get_node_at_pos(x, y + 1) {
-						Some(n) => match *n {
-							Node::Split(i, _) => i,
-							Node::Pipe(i, t) => {
-								if t == 1 { i } else { Ingredient::None }
+							if m.progress >= r.time {
+								m.progress = 0;
+								for (output, ing) in m.output.iter_mut().zip(r.outputs()) {
+									*output = Stack { ingredient: ing, count: output.count + 1 };
+								}
 							}
-							_ => Ingredient::None
 						}
-						None => Ingredient::None
-					}
-				}
-				self.grid[y][x] = Some(node_type(top, left, bottom, l));
-				if x != self.width {
-					self.update(x + 1, y);
-				}
-			}
-			Node::Split(_, o) => {
-				let mut oc = o;
-				let mut ing = Ingredient::None;
-				if x != 0 {
-					match self.get_node_at_pos(x - 1, y) {
-						Some(n) => ing = match *n {
-							Node::In(_) | Node::Merge(_, _) | Node::Comb1(_, _, _, _) | Node::Comb2(_, _, _, _) => n.get_ingredient(self.resipees.clone()),
-							Node::Pipe(i, t) => {
-								if t == 0 || t == 3 || t == 4 {
-									i
-								} else {
-									Ingredient::None
-								}
+						for output in m.output.iter_mut().filter(|s| s.count > 0) {
+							if self.send((x, y), output.ingredient) {
+								output.count -= 1;
 							}
-							_ => Ingredient::None
-						},
-						None => {}
-					}
-				}
-				if x != self.width {
-					oc = match self.get_node_at_pos(x + 1, y) {
-						Some(n) => match *n {
-							Node::PowerRight => true,
-							_ => false
 						}
-						None => false
+						self.grid[y][x] = Some(Node::Machine(m));
 					}
+					_ => {}
 				}
-				self.grid[y][x] = Some(Node::Split(ing, oc));
-				if y != 0 {
-					self.update(x, y - 1);
+			}
+		}
+	}
+	
+	/// Moves on every item that's been in its cell for `LINE_TICKS`, noting any line it gets into
+	/// that's meant to carry something else
+	fn move_items(&mut self) {
+		let mut n = 0;
+		while n < self.items.len() {
+			let item = self.items[n];
+			self.items[n].wait = item.wait.saturating_sub(1);
+			if self.items[n].wait > 0 {
+				n += 1;
+				continue;
+			}
+			match self.hand_on(item.at, Some(item.from), item.ingredient) {
+				Hop::Taken => {
+					self.items.remove(n);
+					continue;
 				}
-				if y != self.height {
-					self.update(x, y + 1);
+				Hop::Along(to) => {
+					self.items[n] = Item { at: to, from: item.at, wait: LINE_TICKS, ..item };
+					let line = self.flow(item.at, to);
+					self.check_mixed(item.at, to, item.ingredient, line);
 				}
+				Hop::Blocked => {}
 			}
-			Node::Merge(_, o) => {
-				let mut oc = o;
-				let mut ing: Option<Ingredient> = None;
-				if y != 0 {
-					match self.get_node_at_pos(x, y - 1) {
-						Some(n) => match *n {
-							Node::Pipe(i, t) => {
-								if t == 2 {
-									ing = Some(i)
-								}
-							}
-							_ => {}
-						}
-						None => {}
+			n += 1;
+		}
+	}
+	
+	/// Marks the cell at `to` as mixed when `ing` coming in from `from` isn't the `line` it's meant to
+	/// carry. Lines that aren't meant to carry anything in particular, such as one past a filter,
+	/// take whatever comes
+	fn check_mixed(&mut self, from: Position, to: Position, ing: Ingredient, line: Ingredient) {
+		if line == ing || line == Ingredient::None || self.mixed.iter().any(|(x, y, _)| (*x, *y) == to) {
+			return;
+		}
+		// Only the first cell of a mixed line is worth a warning
+		if !self.mixed.iter().any(|(x, y, _)| (*x, *y) == from) {
+			self.new_warn(format!("{:?} is getting into the {:?} line at ({}, {})", ing, line, to.0, to.1));
+		}
+		self.mixed.push((to.0, to.1, ing));
+	}
+	
+	/// Hands one `ing` made at `from` on to whatever it feeds, returning whether anything had room
+	/// for it
+	fn send(&mut self, from: Position, ing: Ingredient) -> bool {
+		match self.hand_on(from, None, ing) {
+			Hop::Taken => true,
+			Hop::Along(to) => {
+				self.items.push(Item { ingredient: ing, at: to, from, wait: LINE_TICKS });
+				true
+			}
+			Hop::Blocked => false
+		}
+	}
+	
+	/// Passes one `ing` at `at` into the next cell along. Items made at `at` go wherever it sends
+	/// `ing`, and items that came in from `from` carry on whatever they are. Where the way on
+	/// branches, items take each branch in turn, passing over any that are backed up
+	fn hand_on(&mut self, at: Position, from: Option<Position>, ing: Ingredient) -> Hop {
+		let node = *self.get_node_at_pos(at.0, at.1);
+		// Whatever takes from the side it's on, as a merge takes in from both sides whatever they carry
+		let mut next = self.outputs(at.0, at.1).into_iter().filter(|t| match (from, node) {
+			_ if !self.takes_from(*t, at) => false,
+			// Crossings keep each flow going straight through
+			(Some(f), Some(Node::Cross(_, _, _, _))) => self.side(f, at) == self.side(at, *t),
+			// Filters send what they pass straight on and anything else out of the side
+			(Some(_), Some(Node::Filter(_, pass, f))) => self.side(at, *t) == Some(if ing == pass { f } else { f.clockwise() }),
+			(Some(_), _) => true,
+			(None, _) => self.flow(at, *t) == ing
+		}).collect::<Vec<Position>>();
+		if next.is_empty() {
+			return Hop::Blocked;
+		}
+		let turn = self.turns.get(&at).copied().unwrap_or(0);
+		let skip = turn % next.len();
+		next.rotate_left(skip);
+		for (n, to) in next.into_iter().enumerate() {
+			let (ox, oy) = self.owner(to.0, to.1);
+			let taken = match self.grid[oy][ox] {
+				// Outputs take anything so lines don't jam, but only count what they're receiving. One
+				// that isn't meant to receive anything in particular counts whatever comes first
+				Some(Node::Out(i, count, since)) => {
+					if i == ing {
+						self.grid[to.1][to.0] = Some(Node::Out(i, count + 1, since));
+					} else if i == Ingredient::None {
+						self.grid[to.1][to.0] = Some(Node::Out(ing, 1, self.tick));
+					} else {
+						self.check_mixed(at, to, ing, i);
 					}
+					true
 				}
-				if ing.is_none() && y != self.height {
-					match self.get_node_at_pos(x, y + 1) {
-						Some(n) => match *n {
-							Node::Pipe(i, t) => {
-								if t == 1 {
-									ing = Some(i)
-								}
-							}
-							_ => {}
+				// Machines only take what the side it comes in on is being fed, including the sides of
+				// their back
+				Some(Node::Machine(mut m)) => {
+					let capacity = m.capacity();
+					let slot = self.reads(ox, oy).iter().position(|r| *r == Some(at));
+					match slot.and_then(|n| m.inputs.get_mut(n)).filter(|s| s.ingredient == ing && s.count < capacity) {
+						Some(input) => {
+							input.count += 1;
+							self.grid[oy][ox] = Some(Node::Machine(m));
+							true
 						}
-						None => {}
+						None => continue
 					}
 				}
-				self.grid[y][x] = Some(Node::Merge(if ing.is_some() { ing.unwrap() } else { Ingredient::None }, oc));
-				if x != self.width {
-					self.update(x + 1, y);
-				}
+				_ if self.room(to, at) => false,
+				_ => continue
+			};
+			self.turns.insert(at, turn + n + 1);
+			return if taken { Hop::Taken } else { Hop::Along(to) };
+		}
+		Hop::Blocked
+	}
+	
+	/// Whether the line at `to` has room for another item coming in from `from`. Stalled splits and
+	/// merges don't take anything
+	fn room(&self, to: Position, from: Position) -> bool {
+		let held = |lane: bool| self.items.iter().filter(|i| i.at == to && (!lane || (i.from.1 == to.1) == (from.1 == to.1))).count();
+		match self.get_node_at_pos(to.0, to.1) {
+			Some(Node::Split(_, p, _) | Node::Merge(_, p, _)) => *p && held(false) < LINE_CAPACITY,
+			Some(Node::Cross(_, _, _, _)) => held(true) < LINE_CAPACITY,
+			Some(n) => n.carries() && held(false) < LINE_CAPACITY,
+			None => false
+		}
+	}
+	
+	/// Whether an item in the line at `(x, y)` is waiting on the way on to clear
+	fn congested(&self, x: usize, y: usize) -> bool {
+		self.items.iter().any(|i| i.at == (x, y) && i.wait == 0)
+	}
+	
+	/// How many items the line at `(x, y)` is holding and whether it's backed up
+	fn load(&self, x: usize, y: usize) -> Option<String> {
+		let capacity = match self.get_node_at_pos(x, y) {
+			Some(Node::Cross(_, _, _, _)) => 2 * LINE_CAPACITY,
+			Some(n) if n.carries() => LINE_CAPACITY,
+			_ => return None
+		};
+		Some(format!("holding {} of {}{}", self.items.iter().filter(|i| i.at == (x, y)).count(), capacity,
+			if self.congested(x, y) { ", backed up" } else { "" }))
+	}
+	
+	/// Line cells that have carried something they aren't meant to since the grid last changed,
+	/// along with what
+	pub fn mixed(&self) -> &[(usize, usize, Ingredient)] {
+		&self.mixed
+	}
+	
+	/// Items a minute reaching the output at `(x, y)`, averaged since it started receiving them
+	fn throughput(&self, x: usize, y: usize) -> Option<f32> {
+		match self.get_node_at_pos(x, y) {
+			Some(Node::Out(i, n, since)) if *i != Ingredient::None => {
+				let minutes = (self.tick - since) as f32 / (TICKS_PER_SECOND * 60) as f32;
+				Some(if minutes > 0.0 { *n as f32 / minutes } else { 0.0 })
+			}
+			_ => None
+		}
+	}
+	
+	/// On-grid neighbours of `(x, y)`
+	pub fn neighbours(&self, x: usize, y: usize) -> Vec<Position> {
+		Direction::into_enum_iter().filter_map(|d| d.step((x, y), self.width, self.height)).collect()
+	}
+	
+	/// On-grid cells touching `(x, y)` diagonally
+	pub fn corners(&self, x: usize, y: usize) -> Vec<Position> {
+		Direction::into_enum_iter()
+			.filter_map(|d| d.step((x, y), self.width, self.height).and_then(|p| d.clockwise().step(p, self.width, self.height)))
+			.collect()
+	}
+	
+	/// Where each of the node at `(x, y)`'s inputs comes from, in the order of `Node::inputs` and
+	/// then `Node::back_inputs`. A side only counts when the neighbour there outputs towards this node
+	fn reads(&self, x: usize, y: usize) -> Vec<Option<Position>> {
+		let node = match self.get_node_at_pos(x, y) {
+			Some(n) => n,
+			None => return vec![]
+		};
+		let mut ports = node.inputs().into_iter().map(|side| ((x, y), side)).collect::<Vec<(Position, Direction)>>();
+		if let Some(back) = self.partner(x, y) {
+			ports.extend(node.back_inputs().into_iter().map(|side| (back, side)));
+		}
+		ports.into_iter().map(|(at, side)| {
+			side.step(at, self.width, self.height)
+				.filter(|(nx, ny)| matches!(self.get_node_at_pos(*nx, *ny), Some(n) if n.outputs().contains(&side.opposite())))
+		}).collect()
+	}
+	
+	/// Whether the node the cell at `to` is part of takes in what `from` sends into it
+	fn takes_from(&self, to: Position, from: Position) -> bool {
+		let (ox, oy) = self.owner(to.0, to.1);
+		self.sources(ox, oy).contains(&from)
+	}
+	
+	/// The other cell of a machine two cells long, from either of them
+	fn partner(&self, x: usize, y: usize) -> Option<Position> {
+		match self.get_node_at_pos(x, y) {
+			Some(Node::Back(_, f)) => f.step((x, y), self.width, self.height),
+			Some(n @ Node::Machine(m)) if !n.back_inputs().is_empty() => m.facing.opposite().step((x, y), self.width, self.height),
+			_ => None
+		}
+	}
+	
+	/// Position of the node the cell at `(x, y)` is part of, which for the back of a machine is the
+	/// machine
+	pub fn owner(&self, x: usize, y: usize) -> Position {
+		match self.get_node_at_pos(x, y) {
+			Some(Node::Back(_, _)) => self.partner(x, y).unwrap_or((x, y)),
+			_ => (x, y)
+		}
+	}
+	
+	/// Positions of the neighbours whose ingredient flows into the node at `(x, y)`
+	pub fn sources(&self, x: usize, y: usize) -> Vec<Position> {
+		self.reads(x, y).into_iter().flatten().collect()
+	}
+	
+	/// Positions the node at `(x, y)` passes its ingredient into, whether or not anything there
+	/// takes it
+	pub fn outputs(&self, x: usize, y: usize) -> Vec<Position> {
+		match self.get_node_at_pos(x, y) {
+			Some(n) => n.outputs().iter().filter_map(|d| d.step((x, y), self.width, self.height)).collect(),
+			None => vec![]
+		}
+	}
+	
+	/// Side of `from` that `to` is on, if they're next to each other
+	fn side(&self, from: Position, to: Position) -> Option<Direction> {
+		Direction::into_enum_iter().find(|d| d.step(from, self.width, self.height) == Some(to))
+	}
+	
+	/// What the node at `from` sends into its neighbour at `to`, or into the back of the machine at `to`
+	pub fn flow(&self, from: Position, to: Position) -> Ingredient {
+		let side = self.side(from, to).or_else(|| self.partner(to.0, to.1).and_then(|back| self.side(from, back)));
+		match (self.get_node_at_pos(from.0, from.1), side) {
+			(Some(n), Some(side)) => n.ingredient_to(side, self.resipees.clone()),
+			_ => Ingredient::None
+		}
+	}
+	
+	/// Positions of the neighbours passing their ingredient into `(x, y)`
+	pub fn feeders(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+		self.neighbours(x, y).into_iter().filter(|(nx, ny)| self.outputs(*nx, *ny).contains(&(x, y))).collect()
+	}
+	
+	/// Positions of the neighbours that take their ingredient from `(x, y)`, counting machines
+	/// whose back is next to it
+	pub fn targets(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+		self.neighbours(x, y).into_iter().map(|(nx, ny)| self.owner(nx, ny)).filter(|(nx, ny)| self.sources(*nx, *ny).contains(&(x, y))).collect()
+	}
+	
+	/// Every node feeding into `(x, y)`, or fed by it when `downstream`, including power. Power
+	/// nodes count as feeding each other both ways, so tracing through one takes in its whole
+	/// network. Nearest first
+	pub fn trace_from(&self, x: usize, y: usize, downstream: bool) -> Vec<(usize, usize)> {
+		let mut found = vec![(x, y)];
+		let mut next = 0;
+		while next < found.len() {
+			let (cx, cy) = found[next];
+			let mut linked = if downstream { self.targets(cx, cy) } else { self.sources(cx, cy) };
+			if downstream {
+				linked.extend(self.powers(cx, cy));
+			} else if let Some(p) = self.powered_by(cx, cy) {
+				linked.push(p);
+			}
+			if matches!(self.get_node_at_pos(cx, cy), Some(n) if n.conducts()) {
+				linked.extend(self.neighbours(cx, cy).into_iter().filter(|(nx, ny)| matches!(self.get_node_at_pos(*nx, *ny), Some(n) if n.conducts())));
 			}
-			Node::In(_) => {
-				if x != self.width {
-					self.update(x + 1, y);
+			for pos in linked {
+				if !found.contains(&pos) {
+					found.push(pos);
 				}
 			}
+			next += 1;
+		}
+		found
+	}
+	
+	/// Position of the power node feeding the machine at `(x, y)`, if it needs power and is next to
+	/// a power node that feeds its side. Machines use all their sides for ingredients, so
+	/// generators and power lines also feed the machines on their corners
+	pub fn powered_by(&self, x: usize, y: usize) -> Option<Position> {
+		if self.get_node_at_pos(x, y).as_ref()?.power_draw() == 0 {
+			return None;
 		}
+		self.neighbours(x, y).into_iter().find(|(px, py)| matches!(self.get_node_at_pos(*px, *py),
+			Some(n) if n.conducts() && self.side((*px, *py), (x, y)).is_some_and(|side| n.powers_side(side))))
+			.or_else(|| self.corners(x, y).into_iter().find(|(px, py)| matches!(self.get_node_at_pos(*px, *py),
+				Some(Node::Generator | Node::PowerLine))))
+	}
+	
+	/// Positions of the machines the power node at `(x, y)` is feeding
+	pub fn powers(&self, x: usize, y: usize) -> Vec<Position> {
+		self.neighbours(x, y).into_iter().chain(self.corners(x, y))
+			.filter(|(nx, ny)| self.powered_by(*nx, *ny) == Some((x, y))).collect()
+	}
+	
+	/// Every node on the grid along with its position
+	pub fn nodes(&self) -> impl Iterator<Item = (usize, usize, &Node)> {
+		let width = self.width;
+		self.grid.iter().take(self.height).enumerate().flat_map(move |(y, row)| {
+			row.iter().take(width).enumerate().filter_map(move |(x, n)| n.as_ref().map(|n| (x, y, n)))
+		})
+	}
+	
+	pub fn resipees(&self) -> &HashMap<u32, Resipee> {
+		&self.resipees
+	}
+	
+	pub fn get_node(&self, x: usize, y: usize) -> Option<&Node> {
+		self.get_node_at_pos(x, y).as_ref()
 	}
 	
 	fn get_node_at_pos(&self, x: usize, y: usize) -> &Option<Node> {
@@ -278,25 +604,49 @@ impl Grid {
 		colours.next();
 		stdout.execute(terminal::Clear(terminal::ClearType::All))?
 			.queue(cursor::MoveTo(0, 0))?;
+		if self.rulers {
+			// Cells are two columns wide, so each column label sits over its cell
+			stdout.queue(Print("   "))?;
+			for x in 0..self.width {
+				stdout.queue(Print(format!("{:>2}", x)))?;
+			}
+			stdout.queue(cursor::MoveToNextLine(1))?;
+		}
+		let diagnostics = lint::diagnostics(self);
 		for y in 0..self.height {
+			if self.rulers {
+				stdout.queue(Print(format!("{:>2} ", y)))?;
+			}
 			for x in 0..self.width {
 				let node = self.get_node_at_pos(x, y);
 				stdout
+					.queue(SetBackgroundColor(if (x, y) == self.cursor {
+						Color::DarkGrey
+					} else if diagnostics.iter().chain(self.lint.iter()).any(|(lx, ly, _)| (*lx, *ly) == (x, y)) {
+						Color::DarkRed
+					} else if self.congested(x, y) {
+						Color::DarkYellow
+					} else {
+						Color::Reset
+					}))?
 					.queue(SetForegroundColor(match node {
+						Some(_) if !self.trace.is_empty() && !self.trace.contains(&(x, y)) => Color::DarkGrey,
 						Some(n) => n.col(self.resipees.clone()),
 						None => Color::Reset
 					})
 					)?
-					.queue(Print(format!("{}", match node {
-						Some(n) => n.char(),
-						None => SPACE
-					})))?;
+					.queue(Print(match node {
+						Some(n) => self.glyphs.node(n),
+						None => self.glyphs.empty()
+					}))?;
 			}
+			stdout.queue(SetBackgroundColor(Color::Reset))?;
 			match colours.next() {
+				// Legend of ingredient numbers as taken by ':p;i(n)' and ':r;n', not row numbers
 				Some(ing) => stdout.queue(cursor::MoveRight(1))?
-					.queue(Print(format!("{:>2}", y + 1)))?
+					.queue(Print(format!("#{:<2}", y + 1)))?
 					.queue(SetForegroundColor(ing.get_colour()))?
-					.queue(Print(ing.char()))?
+					.queue(Print(self.glyphs.ingredient(ing)))?
 					.queue(SetForegroundColor(Color::Reset))?
 					.queue(Print(format!("{:?}", ing)))?,
 				None => &mut stdout
@@ -304,21 +654,21 @@ impl Grid {
 			stdout.queue(cursor::MoveToNextLine(1))?;
 		}
 		
-		stdout.queue(cursor::MoveTo(0, (self.height + 1) as u16))?
+		let (cols, _) = terminal::size()?;
+		let (_, top) = self.cell_pos(0, 0);
+		stdout.queue(cursor::MoveTo(0, top + self.height as u16))?
 			.queue(SetForegroundColor(Color::Reset))?
-			.queue(Print(current_command));
-		for console_line in 0..3 {
-			stdout.queue(cursor::MoveToNextLine(1))?
-				.queue(SetForegroundColor(if *self.console_is_err.get(console_line).unwrap() {
-					Color::Red
-				} else {
-					Color::Reset
-				}))?
-				.queue(Print(match self.console.get(console_line).unwrap() {
-					Some(s) => s.clone(),
-					None => String::new()
-				}))?
-				.queue(SetForegroundColor(Color::Reset));
+			.queue(Print(self.status(&diagnostics).chars().take(cols as usize).collect::<String>()))?
+			.queue(cursor::MoveTo(0, top + (self.height + 1) as u16))?
+			.queue(Print(current_command))?;
+		let mut lines = self.console.latest(3);
+		for _ in 0..3 {
+			stdout.queue(cursor::MoveToNextLine(1))?;
+			if let Some(e) = lines.next() {
+				stdout.queue(SetForegroundColor(e.level.get_colour()))?
+					.queue(Print(e.message.clone()))?
+					.queue(SetForegroundColor(Color::Reset))?;
+			}
 		}
 		
 		stdout.flush()?;
@@ -326,26 +676,86 @@ impl Grid {
 		Ok(())
 	}
 	
-	pub fn execute_command(&mut self, current_command: String) {
-		let mut chars = current_command.chars();
-		match chars.nth(0) {
-			Some(c) => {
-				if c != ':' { return; }
+	/// Terminal column and row of the cell at `(x, y)`, shifted along by the rulers when shown
+	fn cell_pos(&self, x: usize, y: usize) -> (u16, u16) {
+		if self.rulers {
+			(3 + 2 * x as u16, 1 + y as u16)
+		} else {
+			(2 * x as u16, y as u16)
+		}
+	}
+	
+	/// Status bar text for the cell under the cursor, followed by any problems with it
+	fn status(&self, diagnostics: &[(usize, usize, String)]) -> String {
+		let (x, y) = self.cursor;
+		let mut status = match self.get_node_at_pos(x, y) {
+			Some(n) => match n.resipee_status(self.resipees.clone()) {
+				Some(r) => format!("({}, {}) {} | {}", x, y, n.info(self.resipees.clone()), r),
+				None => format!("({}, {}) {}", x, y, n.info(self.resipees.clone()))
 			}
-			None => return
+			None => format!("({}, {}) Empty", x, y)
+		};
+		if let Some(network) = power::network_at(self, x, y) {
+			status.push_str(" | ");
+			status.push_str(&network.describe());
 		}
-		// Have to get the 0th character because the previous char ':' was removed, shifting the op_code to index 0
-		let op_code = match chars.nth(0) {
+		if let Some(rate) = self.throughput(x, y) {
+			status.push_str(&format!(" | {:.1} a minute", rate));
+		}
+		if let Some(load) = self.load(x, y) {
+			status.push_str(" | ");
+			status.push_str(&load);
+		}
+		let mut problems: Vec<&str> = vec![];
+		for (_, _, problem) in diagnostics.iter().chain(self.lint.iter()).filter(|(px, py, _)| (*px, *py) == (x, y)) {
+			if !problems.contains(&problem.as_str()) {
+				problems.push(problem);
+				status.push_str(" | ! ");
+				status.push_str(problem);
+			}
+		}
+		status
+	}
+	
+	/// Moves the cursor for an arrow key, keeping it on the grid. Returns false for any other key
+	pub fn move_cursor(&mut self, key: KeyCode) -> bool {
+		let (x, y) = &mut self.cursor;
+		match key {
+			KeyCode::Left => if *x != 0 {
+				*x -= 1
+			}
+			KeyCode::Right => if *x != self.width - 1 {
+				*x += 1
+			}
+			KeyCode::Up => if *y != 0 {
+				*y -= 1
+			}
+			KeyCode::Down => if *y != self.height - 1 {
+				*y += 1
+			}
+			_ => return false
+		}
+		true
+	}
+	
+	pub fn execute_command(&mut self, current_command: String) {
+		let command = match current_command.strip_prefix(':') {
 			Some(c) => c,
 			None => return
 		};
-		match op_code {
-			'q' => {
-				execute!(stdout(), LeaveAlternateScreen, Show);
+		// Commands are a name followed by arguments separated from it by either a ';' or a space
+		let (name, args) = match command.find([';', ' ']) {
+			Some(i) => (&command[..i], Some(&command[i + 1..])),
+			None => (command, None)
+		};
+		match name {
+			"q" => {
+				// Leaving anyway, so there's nothing to do if the terminal can't be restored
+				execute!(stdout(), LeaveAlternateScreen, Show).ok();
 				exit(0)
 			}
-			'p' => {
-				let cap = match Regex::new(r";(\w+)(\((\w{1,2})\))?").unwrap().captures(&*current_command) {
+			"p" => {
+				let cap = match Regex::new(r";(\w+)(\((\w{1,3})\))?").unwrap().captures(&current_command) {
 					Some(c) => c,
 					None => {
 						self.new_error(format!("Unknown command '{}'", current_command));
@@ -359,14 +769,22 @@ impl Grid {
 							return
 						}
 						Node::In(match &cap[3].parse::<u16>() {
-							Ok(v) => Ingredient::u16_to_ing(*v),
+							Ok(v) => match Ingredient::u16_to_ing(*v) {
+								Some(i) => i,
+								None => {
+									self.new_error(format!("No ingredient number {}", v));
+									return;
+								}
+							},
 							Err(_) => {
 								self.new_error(format!("Cannot parse '{}' as u16", &cap[3]));
 								return;
 							}
 						})
 					}
-					"o" => Node::Out(Ingredient::None),
+					"o" => Node::Out(Ingredient::None, 0, self.tick),
+					"g" => Node::Generator,
+					"w" => Node::PowerLine,
 					"P" => {
 						if cap.get(3).is_none() {
 							self.new_error("Expected 3 arguments for ':p;P' command".to_string());
@@ -381,94 +799,337 @@ impl Grid {
 							}
 						}
 					}
-					"c1" => {
+					code if machines::machine_list().iter().any(|m| m.code == code) => {
+						let spec = machines::machine_list().into_iter().find(|m| m.code == code).unwrap();
 						if cap.get(3).is_none() {
-							self.new_error("Expected 3 arguments for ':p;c1' command".to_string());
+							self.new_error(format!("Expected 3 arguments for ':p;{}' command", code));
 							return
 						}
-						match &cap[3].parse::<u8>() {
-							Ok(v) => if v > &0 && v < &3 {
-								Node::Comb1(Ingredient::None, Ingredient::None, Ingredient::None, *v - 1)
+						// Machine codes are the level then optionally the way the machine faces
+						let mut code = cap[3].chars();
+						let level = code.next().and_then(|c| c.to_digit(10));
+						let facing = match Grid::facing(code.as_str()) {
+							Some(f) => f,
+							None => {
+								self.new_error(format!("Unknown facing '{}'", code.as_str()));
+								return;
+							}
+						};
+						match level {
+							Some(v) => if v > 0 && v <= spec.levels as u32 {
+								Node::Machine(Machine::new(spec.name, v as u8 - 1, facing))
 							} else {
-								self.new_error(format!("Comb1 has a max level of level 2. Given level {}", v));
+								self.new_error(format!("{} has a max level of level {}. Given level {}", spec.name, spec.levels, v));
 								return;
 							},
-							Err(_) => {
-								self.new_error(format!("Cannot parse '{}' as u16", &cap[3]));
+							None => {
+								self.new_error(format!("Cannot parse '{}' as a level", &cap[3]));
 								return;
 							}
 						}
 					}
-					"c2" => {
+					"s" | "m" => match Grid::facing(cap.get(3).map_or("", |m| m.as_str())) {
+						Some(f) if &cap[1] == "s" => Node::Split(Ingredient::None, false, f),
+						Some(f) => Node::Merge(Ingredient::None, false, f),
+						None => {
+							self.new_error(format!("Unknown facing '{}'", &cap[3]));
+							return;
+						}
+					}
+					"p" => {
 						if cap.get(3).is_none() {
-							self.new_error("Expected 3 arguments for ':p;c2' command".to_string());
+							self.new_error("Expected 3 arguments for ':p;p' command".to_string());
 							return
 						}
-						match &cap[3].parse::<u8>() {
-							Ok(v) => if v > &0 && v < &3 {
-								Node::Comb2(Ingredient::None, Ingredient::None, Ingredient::None, *v - 1)
-							} else {
-								self.new_error(format!("Comb2 has a max level of level 2. Given level {}", v));
+						// Pipe codes are the side the pipe comes in from then the side it goes out of
+						let mut sides = cap[3].chars().map(Direction::from_letter);
+						match (sides.next().flatten(), sides.next().flatten()) {
+							(Some(from), Some(to)) if from != to => Node::Pipe(Ingredient::None, from, to),
+							_ => {
+								self.new_error(format!("Unknown pipe code '{}'", &cap[3]));
 								return;
-							},
-							Err(_) => {
-								self.new_error(format!("Cannot parse '{}' as u16", &cap[3]));
+							}
+						}
+					}
+					"f" => {
+						if cap.get(3).is_none() {
+							self.new_error("Expected 3 arguments for ':p;f' command".to_string());
+							return
+						}
+						// Filter codes are the number of the ingredient to let through then optionally the way it goes
+						let digits = cap[3].chars().take_while(|c| c.is_ascii_digit()).count();
+						let pass = match cap[3][..digits].parse::<u16>().ok().and_then(Ingredient::u16_to_ing) {
+							Some(i) if i != Ingredient::None => i,
+							_ => {
+								self.new_error(format!("No ingredient number '{}'", &cap[3][..digits]));
+								return;
+							}
+						};
+						match Grid::facing(&cap[3][digits..]) {
+							Some(f) => Node::Filter(Ingredient::None, pass, f),
+							None => {
+								self.new_error(format!("Unknown facing '{}'", &cap[3][digits..]));
 								return;
 							}
 						}
 					}
-					"s" => Node::Split(Ingredient::None, false),
-					"m" => Node::Merge(Ingredient::None, false),
-					"p" => {
+					"t" => {
 						if cap.get(3).is_none() {
-							self.new_error("Expected 3 arguments for ':p;p' command".to_string());
+							self.new_error("Expected 3 arguments for ':p;t' command".to_string());
 							return
 						}
-						Node::Pipe(Ingredient::None, match &cap[3] {
-						"lr" => 0,
-						"lu" => 1,
-						"ld" => 2,
-						"dr" => 3,
-						"ur" => 4,
-						_ => {
-							self.new_error(format!("Unknown pipe code '{}'", &cap[3]));
-							return;
+						// Tee codes are the side the flow comes in from
+						match cap[3].chars().next().and_then(Direction::from_letter) {
+							Some(from) if cap[3].len() == 1 => Node::Tee(Ingredient::None, from),
+							_ => {
+								self.new_error(format!("Unknown tee code '{}'", &cap[3]));
+								return;
+							}
+						}
+					}
+					"x" => {
+						if cap.get(3).is_none() {
+							self.new_error("Expected 3 arguments for ':p;x' command".to_string());
+							return
 						}
-					})}
+						// Crossing codes are the way the horizontal flow goes then the way the vertical one goes
+						let mut sides = cap[3].chars().map(Direction::from_letter);
+						match (sides.next().flatten(), sides.next().flatten()) {
+							(Some(h @ (Direction::Left | Direction::Right)), Some(v @ (Direction::Up | Direction::Down))) =>
+								Node::Cross(Ingredient::None, h, Ingredient::None, v),
+							_ => {
+								self.new_error(format!("Unknown crossing code '{}'", &cap[3]));
+								return;
+							}
+						}
+					}
 					_ => {
 						self.new_error(format!("Unknown node command '{}'", current_command));
 						return;
 					}
 				};
-				self.place(node);
-			}
-			'd' => { self.delete(); }
-			'i' => { self.info(); }
-			'r' => {
-				match chars.nth(0) {
-					Some(c) => if c != ';' {
-						self.new_error(format!("Expected ';', found {}", c));
+				if let Err(e) = self.place(node) {
+					self.new_error(format!("Could not place node: {}", e));
+				}
+			}
+			"d" => if let Err(e) = self.delete() {
+				self.new_error(format!("Could not delete node: {}", e));
+			}
+			"i" => if let Err(e) = self.info() {
+				self.new_error(format!("Could not show node info: {}", e));
+			}
+			"upgrade" => if let Err(e) = self.upgrade() {
+				self.new_error(format!("Could not upgrade node: {}", e));
+			}
+			"rot" => {
+				let clockwise = match args {
+					None | Some("cw") => true,
+					Some("ccw") => false,
+					Some(a) => {
+						self.new_error(format!("Expected 'cw' or 'ccw' after ':rot'; found '{}'", a));
 						return;
 					}
+				};
+				if let Err(e) = self.rotate(clockwise) {
+					self.new_error(format!("Could not rotate node: {}", e));
+				}
+			}
+			"r" => {
+				let args = match args {
+					Some(a) => a,
 					None => {
 						self.new_error("Expected a ';' after ':r'".to_string());
 						return;
 					}
-				}
-				match chars.as_str().parse::<u16>() {
+				};
+				match args.parse::<u16>() {
 					Ok(v) => {
-						let out = Ingredient::u16_to_ing(v);
-						let (ing_vec, mac_req) = out.resipee();
-						let ing_vec = ing_vec.iter().map(|arg| format!("{:?}", arg)).collect::<Vec<String>>();
-						self.new_info(format!("{:?} -> Ingredients: {} | {}", out, ing_vec.join(", "), match mac_req {
-							Some(mr) => format!("{:?}", mr),
-							None => "Base ingredient. Comes form input only".to_string()
-						}))
+						let out = match Ingredient::u16_to_ing(v) {
+							Some(i) => i,
+							None => {
+								self.new_error(format!("No ingredient number {}", v));
+								return;
+							}
+						};
+						let mut makers = self.resipees.values().filter(|r| r.output == out).collect::<Vec<&Resipee>>();
+						makers.sort_by_key(|r| (r.machine.name(), r.machine.min_level));
+						let makers = makers.iter().map(|r| format!("{:?}", r)).collect::<Vec<String>>();
+						if makers.is_empty() {
+							self.new_info(format!("{:?} is a base ingredient. Comes from input only", out));
+						}
+						for r in makers {
+							self.new_info(r);
+						}
+						let sources = self.resipees.values().filter(|r| r.byproducts.contains(&out)).map(|r| format!("{:?} is a byproduct of '{:?}'", out, r)).collect::<Vec<String>>();
+						for source in sources {
+							self.new_info(source);
+						}
 					}
-					Err(_) => {
-						self.new_error(format!("Cannot parse '{}' as u16", chars.as_str()));
+					Err(_) => self.new_error(format!("Cannot parse '{}' as u16", args))
+				}
+			}
+			"b" | "book" => {
+				if let Err(e) = RecipeBook::new(&self.resipees, self.glyphs, args.unwrap_or("")).view() {
+					self.new_error(format!("Could not show recipe book: {}", e));
+				}
+			}
+			"chain" => {
+				let ing = match args.and_then(Ingredient::from_name) {
+					Some(i) => i,
+					None => {
+						self.new_error(format!("Expected an ingredient name after ':chain'; found '{}'", args.unwrap_or("")));
+						return;
+					}
+				};
+				let chain = match Chain::new(ing, &self.resipees) {
+					Ok(c) => c,
+					Err(e) => {
+						self.new_error(e);
+						return;
+					}
+				};
+				// The console shows the newest line first, so the summary goes in last and the tree is
+				// left in order in the log
+				for line in chain.tree {
+					self.new_info(line);
+				}
+				self.new_info(format!("{:?} needs {} | machines: {}{}", ing,
+					chain.raw.iter().map(|(i, n)| format!("{} {:?}", n, i)).collect::<Vec<String>>().join(", "),
+					if chain.machines.is_empty() {
+						String::from("none")
+					} else {
+						chain.machines.iter().map(|(m, l, n)| format!("{} {} (level {})", n, m, l + 1)).collect::<Vec<String>>().join(", ")
+					},
+					if chain.catalysts.is_empty() {
+						String::new()
+					} else {
+						format!(" | catalysts: {}", chain.catalysts.iter().map(|i| format!("{:?}", i)).collect::<Vec<String>>().join(", "))
+					}));
+			}
+			"uses" => {
+				let ing = match args.and_then(Ingredient::from_name) {
+					Some(i) => i,
+					None => {
+						self.new_error(format!("Expected an ingredient name after ':uses'; found '{}'", args.unwrap_or("")));
 						return;
 					}
+				};
+				let uses = resipee::consumers(ing, &self.resipees).iter().map(|r| format!("{:?}", r)).collect::<Vec<String>>();
+				if uses.is_empty() {
+					self.new_info(format!("Nothing uses {:?}", ing));
+				} else {
+					for r in uses {
+						self.new_info(r);
+					}
+				}
+			}
+			"craftable" => {
+				let mut sources = vec![];
+				let mut machines: Vec<(&str, u8)> = vec![];
+				for node in self.grid.iter().flatten().flatten() {
+					match node {
+						Node::In(i) if *i != Ingredient::None && !sources.contains(i) => sources.push(*i),
+						Node::Machine(m) => match machines.iter_mut().find(|(name, _)| *name == m.name) {
+							Some((_, level)) => *level = (*level).max(m.level),
+							None => machines.push((m.name, m.level))
+						}
+						_ => {}
+					}
+				}
+				let made = resipee::reachable(&sources, &machines, &self.resipees);
+				self.new_info(if made.is_empty() {
+					String::from("Nothing can be made from the inputs and machines on the grid")
+				} else {
+					format!("Can make: {}", made.iter().map(|i| format!("{:?}", i)).collect::<Vec<String>>().join(", "))
+				});
+			}
+			"export-recipes" => match args {
+				Some(path) if !path.is_empty() => match std::fs::write(path, dot::resipees_to_dot(&self.resipees)) {
+					Ok(_) => self.new_info(format!("Wrote resipee graph to '{}'", path)),
+					Err(e) => self.new_error(format!("Cannot write '{}': {}", path, e))
+				}
+				_ => self.new_error("Expected a file path after ':export-recipes'".to_string())
+			}
+			"export-factory" => match args {
+				Some(path) if !path.is_empty() => match std::fs::write(path, dot::grid_to_dot(self)) {
+					Ok(_) => self.new_info(format!("Wrote factory graph to '{}'", path)),
+					Err(e) => self.new_error(format!("Cannot write '{}': {}", path, e))
+				}
+				_ => self.new_error("Expected a file path after ':export-factory'".to_string())
+			}
+			"validate" => {
+				let issues = validate::validate(&resipee::resipee_list());
+				if issues.is_empty() {
+					self.new_info("No problems found with the resipees".to_string());
+				}
+				for issue in issues {
+					self.new_warn(issue);
+				}
+			}
+			"balance" => {
+				for line in validate::balance(&self.resipees) {
+					self.new_info(line);
+				}
+			}
+			"lint" => {
+				self.lint = lint::lint(self);
+				for (x, y, problem) in self.lint.clone() {
+					self.new_warn(format!("({}, {}) {}", x, y, problem));
+				}
+				self.new_info(format!("Lint found {} problem{}", self.lint.len(), if self.lint.len() == 1 { "" } else { "s" }));
+			}
+			"t" | "trace" => {
+				let downstream = match args.unwrap_or("up") {
+					"up" | "" => false,
+					"down" => true,
+					"clear" => {
+						self.trace.clear();
+						return;
+					}
+					a => {
+						self.new_error(format!("Expected 'up', 'down' or 'clear'; found '{}'", a));
+						return;
+					}
+				};
+				let (x, y) = match self.select() {
+					Ok(Some(pos)) => pos,
+					Ok(None) => return,
+					Err(e) => {
+						self.new_error(format!("Could not select a node: {}", e));
+						return;
+					}
+				};
+				if self.get_node_at_pos(x, y).is_none() {
+					self.new_error(format!("No node to trace at ({}, {})", x, y));
+					return;
+				}
+				self.trace = self.trace_from(x, y, downstream);
+				for (tx, ty) in self.trace.clone() {
+					let n = self.get_node_at_pos(tx, ty).unwrap();
+					self.new_info(format!("({}, {}) {} carrying {:?}", tx, ty, n.name(), n.get_ingredient(self.resipees.clone())));
+				}
+				self.new_info(format!("Traced {} node{} {} from ({}, {}). ':trace;clear' to stop", self.trace.len(),
+					if self.trace.len() == 1 { "" } else { "s" }, if downstream { "downstream" } else { "upstream" }, x, y));
+			}
+			"rulers" => self.rulers = !self.rulers,
+			"glyphs" => match args.and_then(GlyphSet::parse) {
+				Some(g) => self.glyphs = g,
+				None => self.new_error(format!("Expected 'font' or 'box' after ':glyphs'; found '{}'", args.unwrap_or("")))
+			}
+			"l" | "log" => {
+				let mut args = args.unwrap_or("").splitn(2, ';');
+				let level = match args.next() {
+					Some("") | None => Level::Info,
+					Some(l) => match Level::parse(l) {
+						Some(l) => l,
+						None => {
+							self.new_error(format!("Unknown log level '{}'. Expected one of 'info', 'warn' or 'error'", l));
+							return;
+						}
+					}
+				};
+				if let Err(e) = self.console.view(level, args.next().unwrap_or("")) {
+					self.new_error(format!("Could not show log: {}", e));
 				}
 			}
 			_ => self.new_error(format!("Unknown command '{}'", current_command))
@@ -476,180 +1137,296 @@ impl Grid {
 	}
 	
 	fn new_error(&mut self, err: String) {
-		self.console = [Some(err), self.console.get(0).unwrap().clone(), self.console.get(1).unwrap().clone()];
-		self.console_is_err = [true, self.console_is_err.get(0).unwrap().clone(), self.console_is_err.get(1).unwrap().clone()];
+		self.console.push(Level::Error, err)
+	}
+	fn new_warn(&mut self, warn: String) {
+		self.console.push(Level::Warn, warn)
 	}
 	fn new_info(&mut self, info: String) {
-		self.console = [Some(info), self.console.get(0).unwrap().clone(), self.console.get(1).unwrap().clone()];
-		self.console_is_err = [false, self.console_is_err.get(0).unwrap().clone(), self.console_is_err.get(1).unwrap().clone()];
+		self.console.push(Level::Info, info)
 	}
 	
 	fn place(&mut self, node: Node) -> result<()> {
-		execute!(stdout(), Show)?;
-		let mut x = 0u16;
-		let mut y = 0u16;
-		loop {
-			if poll(Duration::from_millis(500))? {
-				match read()? {
-					Event::Key(key) => {
-						match key.code {
-							KeyCode::Left => if x != 0 {
-								x -= 1
-							}
-							KeyCode::Right => if x != (self.width - 1) as u16 {
-								x += 1
-							}
-							KeyCode::Up => if y != 0 {
-								y -= 1
-							}
-							KeyCode::Down => if y != self.height as u16 {
-								y += 1
-							}
-							KeyCode::Enter => {
-								self.set_node(x as usize, y as usize, node);
-								self.update(x as usize, y as usize);
-								execute!(stdout(), cursor::Hide)?;
-								break;
-							}
-							KeyCode::Esc => {
-								execute!(stdout(), cursor::Hide)?;
-								break;
-							}
-							_ => {}
-						}
-					}
-					_ => {}
-				}
+		if let Some((x, y)) = self.select()? {
+			if let Some(previous) = self.get_node_at_pos(x, y) {
+				self.new_warn(format!("Replaced {} at ({}, {})", previous.name(), x, y));
+			}
+			if let Err(e) = self.set_node(x, y, node) {
+				self.new_warn(e);
 			}
-			self.print_to_stdout(String::new())?;
-			let mut stdout = stdout();
-			stdout.queue(cursor::MoveTo(2 * x, y))?;
-			stdout.flush()?
 		}
 		Ok(())
 	}
 	
 	fn delete(&mut self) -> result<()> {
-		let mut stdout = stdout();
-		execute!(stdout, Show)?;
-		let mut x = 0u16;
-		let mut y = 0u16;
-		loop {
-			if poll(Duration::from_millis(500))? {
-				match read()? {
-					Event::Key(key) => {
-						match key.code {
-							KeyCode::Left => if x != 0 {
-								x -= 1
-							}
-							KeyCode::Right => if x != (self.width - 1) as u16 {
-								x += 1
-							}
-							KeyCode::Up => if y != 0 {
-								y -= 1
-							}
-							KeyCode::Down => if y != self.height as u16 {
-								y += 1
-							}
-							KeyCode::Enter => {
-								let previous = self.get_node_at_pos(x as usize, y as usize).clone();
-								self.grid[y as usize][x as usize] = None;
-								match previous {
-									Some(n) => match n {
-										Node::In(_) | Node::PowerRight | Node::Merge(_, _) | Node::Comb1(_, _, _, _) | Node::Comb2(_, _, _, _) => {
-											if x != (self.width - 1) as u16 {
-												self.update((x + 1) as usize, y as usize);
-											}
-										}
-										Node::PowerLeft | Node::Split(_, _) => {
-											if x != 0 {
-												self.update((x - 1) as usize, y as usize);
-											}
-										}
-										Node::Pipe(_, t) => match t {
-											0 | 3 | 4 => {
-												if x != self.width as u16 {
-													self.update((x + 1) as usize, y as usize);
-												}
-											}
-											1 => {
-												if y != 0 {
-													self.update(x as usize, (y - 1) as usize);
-												}
-											}
-											_ => {
-												if y != self.height as u16 {
-													self.update(x as usize, (y + 1) as usize);
-												}
-											}
-										}
-										_ => {}
-									}
-									None => {}
-								}
-								self.update(x as usize, y as usize);
-								execute!(stdout, cursor::Hide)?;
-								break;
-							}
-							KeyCode::Esc => {
-								execute!(stdout, cursor::Hide)?;
-								break;
-							}
-							_ => {}
-						}
-					}
-					_ => {}
+		if let Some((x, y)) = self.select()? {
+			self.remove_node(x, y);
+		}
+		Ok(())
+	}
+	
+	fn rotate(&mut self, clockwise: bool) -> result<()> {
+		if let Some((x, y)) = self.select()? {
+			let (x, y) = self.owner(x, y);
+			match self.get_node_at_pos(x, y).map(|n| (n.name(), n.rotated(clockwise))) {
+				Some((_, Some(n))) => if let Err(e) = self.set_node(x, y, n) {
+					self.new_warn(e);
 				}
+				Some((name, None)) => self.new_warn(format!("{} can't be rotated", name)),
+				None => self.new_warn(format!("No node at ({}, {}) to rotate", x, y))
 			}
-			stdout.queue(cursor::MoveTo(2 * x, y))?;
-			stdout.flush()?
 		}
 		Ok(())
 	}
 	
-	fn info(&mut self) -> result<()> {
+	/// Raises the selected machine a level, keeping everything else about it
+	fn upgrade(&mut self) -> result<()> {
+		if let Some((x, y)) = self.select()? {
+			let (x, y) = self.owner(x, y);
+			let node = match self.get_node_at_pos(x, y) {
+				Some(n) => *n,
+				None => {
+					self.new_warn(format!("No node at ({}, {}) to upgrade", x, y));
+					return Ok(());
+				}
+			};
+			match (node.level(), machines::spec(node.name())) {
+				(Some(l), Some(spec)) if l + 1 < spec.levels => {
+					self.set_node(x, y, node.with_level(l + 1)).ok();
+					self.new_info(format!("Upgraded {} at ({}, {}) to level {}", node.name(), x, y, l + 2));
+				}
+				(Some(_), _) => self.new_warn(format!("{} at ({}, {}) is already at its max level", node.name(), x, y)),
+				(None, _) => self.new_warn(format!("{} can't be upgraded", node.name()))
+			}
+		}
+		Ok(())
+	}
+	
+	/// The way a machine faces from the letter after its level, facing right when there's none
+	fn facing(code: &str) -> Option<Direction> {
+		let mut letters = code.chars();
+		match (letters.next(), letters.next()) {
+			(None, _) => Some(Direction::Right),
+			(Some(c), None) => Direction::from_letter(c),
+			_ => None
+		}
+	}
+	
+	/// Lets the player move the cursor to a cell, returning it on Enter or `None` on Esc
+	fn select(&mut self) -> result<Option<(usize, usize)>> {
 		let mut stdout = stdout();
 		execute!(stdout, Show)?;
-		let mut x = 0u16;
-		let mut y = 0u16;
 		loop {
 			if poll(Duration::from_millis(500))? {
-				match read()? {
-					Event::Key(key) => {
-						match key.code {
-							KeyCode::Left => if x != 0 {
-								x -= 1
-							}
-							KeyCode::Right => if x != (self.width - 1) as u16 {
-								x += 1
-							}
-							KeyCode::Up => if y != 0 {
-								y -= 1
-							}
-							KeyCode::Down => if y != self.height as u16 {
-								y += 1
-							}
-							KeyCode::Enter => {
-								self.new_info(match self.get_node_at_pos(x as usize, y as usize) {
-									Some(n) => n.info(self.resipees.clone()),
-									None => String::from("No node")
-								});
-								execute!(stdout, cursor::Hide)?;
-								break;
-							}
-							KeyCode::Esc => {
-								execute!(stdout, cursor::Hide)?;
-								break;
-							}
-							_ => {}
+				if let Event::Key(key) = read()? {
+					match key.code {
+						k if self.move_cursor(k) => {}
+						KeyCode::Enter => {
+							execute!(stdout, cursor::Hide)?;
+							return Ok(Some(self.cursor));
+						}
+						KeyCode::Esc => {
+							execute!(stdout, cursor::Hide)?;
+							return Ok(None);
 						}
+						_ => {}
 					}
-					_ => {}
 				}
 			}
-			stdout.queue(cursor::MoveTo(2 * x, y))?;
+			self.print_to_stdout(String::new())?;
+			let (col, row) = self.cell_pos(self.cursor.0, self.cursor.1);
+			stdout.queue(cursor::MoveTo(col, row))?;
 			stdout.flush()?
 		}
+	}
+	
+	fn info(&mut self) -> result<()> {
+		if let Some((x, y)) = self.select()? {
+			let mut info = match (self.get_node_at_pos(x, y), power::network_at(self, x, y), self.throughput(x, y)) {
+				(Some(n), Some(network), _) => format!("{}. {}", n.info(self.resipees.clone()), network.describe()),
+				(Some(n), None, Some(rate)) => format!("{}, throughput: {:.1} a minute", n.info(self.resipees.clone()), rate),
+				(Some(n), None, None) => n.info(self.resipees.clone()),
+				(None, _, _) => String::from("No node")
+			};
+			if let Some(load) = self.load(x, y) {
+				info.push_str(&format!(", {}", load));
+			}
+			self.new_info(info);
+		}
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use Direction::*;
+	
+	fn grid() -> Grid {
+		Grid::new(10, 10, resipee::generate_resipees())
+	}
+	
+	fn pipe(from: Direction, to: Direction) -> Node {
+		Node::Pipe(Ingredient::None, from, to)
+	}
+	
+	fn run(grid: &mut Grid, ticks: u32) {
+		for _ in 0..ticks {
+			grid.tick();
+		}
+	}
+	
+	#[test]
+	fn pipe_loop_is_reported_as_feedback() {
+		let mut g = grid();
+		g.set_node(1, 1, pipe(Down, Right)).unwrap();
+		g.set_node(2, 1, pipe(Left, Down)).unwrap();
+		g.set_node(2, 2, pipe(Up, Left)).unwrap();
+		g.set_node(1, 2, pipe(Right, Up)).unwrap();
+		assert!(g.console.latest(1).any(|e| e.message.starts_with("Feedback loop through")));
+		// Nothing feeds the loop, so it settles on carrying nothing
+		for (x, y) in [(1, 1), (2, 1), (2, 2), (1, 2)] {
+			assert!(matches!(g.get_node(x, y), Some(Node::Pipe(Ingredient::None, _, _))));
+		}
+	}
+	
+	#[test]
+	fn deleting_a_pipe_clears_downstream() {
+		let mut g = grid();
+		g.set_node(0, 0, Node::In(Ingredient::Milk)).unwrap();
+		for x in 1..4 {
+			g.set_node(x, 0, pipe(Left, Right)).unwrap();
+		}
+		g.set_node(4, 0, Node::Out(Ingredient::None, 0, 0)).unwrap();
+		assert!(matches!(g.get_node(4, 0), Some(Node::Out(Ingredient::Milk, _, _))));
+		g.remove_node(2, 0);
+		assert!(matches!(g.get_node(1, 0), Some(Node::Pipe(Ingredient::Milk, _, _))));
+		assert!(matches!(g.get_node(3, 0), Some(Node::Pipe(Ingredient::None, _, _))));
+		assert!(matches!(g.get_node(4, 0), Some(Node::Out(Ingredient::None, _, _))));
+	}
+	
+	#[test]
+	fn powered_machine_delivers_its_output() {
+		let mut g = grid();
+		g.set_node(3, 3, Node::Machine(Machine::new("Comb1", 0, Right))).unwrap();
+		g.set_node(2, 2, Node::In(Ingredient::Water)).unwrap();
+		g.set_node(3, 2, pipe(Left, Down)).unwrap();
+		g.set_node(2, 3, Node::In(Ingredient::Hot)).unwrap();
+		g.set_node(2, 4, Node::In(Ingredient::Milk)).unwrap();
+		g.set_node(3, 4, pipe(Left, Up)).unwrap();
+		g.set_node(4, 2, Node::Generator).unwrap();
+		g.set_node(4, 3, Node::Out(Ingredient::None, 0, 0)).unwrap();
+		run(&mut g, 20 * TICKS_PER_SECOND);
+		match g.get_node(4, 3) {
+			Some(Node::Out(Ingredient::Coffee, n, _)) => assert!(*n > 0),
+			_ => panic!("Expected Coffee at the output")
+		}
+	}
+	
+	#[test]
+	fn full_line_holds_back_its_input() {
+		let mut g = grid();
+		g.set_node(0, 0, Node::In(Ingredient::Metal)).unwrap();
+		g.set_node(1, 0, pipe(Left, Right)).unwrap();
+		g.set_node(2, 0, pipe(Left, Right)).unwrap();
+		run(&mut g, 20 * TICKS_PER_SECOND);
+		assert_eq!(g.items.len(), 2 * LINE_CAPACITY);
+		assert!(g.congested(2, 0));
+		run(&mut g, 20 * TICKS_PER_SECOND);
+		assert_eq!(g.items.len(), 2 * LINE_CAPACITY);
+	}
+	
+	#[test]
+	fn output_only_counts_its_own_ingredient() {
+		let mut g = grid();
+		g.set_node(0, 1, Node::In(Ingredient::Hot)).unwrap();
+		g.set_node(1, 1, pipe(Left, Down)).unwrap();
+		g.set_node(0, 3, Node::In(Ingredient::Cold)).unwrap();
+		g.set_node(1, 3, pipe(Left, Up)).unwrap();
+		g.set_node(1, 2, Node::Merge(Ingredient::None, false, Right)).unwrap();
+		g.set_node(2, 1, Node::Generator).unwrap();
+		g.set_node(2, 2, Node::Out(Ingredient::None, 0, 0)).unwrap();
+		run(&mut g, 20 * TICKS_PER_SECOND);
+		match g.get_node(2, 2) {
+			Some(Node::Out(Ingredient::Hot, n, _)) => assert!(*n > 0 && *n <= 20),
+			_ => panic!("Expected Hot at the output")
+		}
+		assert!(g.mixed().iter().any(|(x, y, i)| (*x, *y, *i) == (2, 2, Ingredient::Cold)));
+	}
+	
+	#[test]
+	fn merge_traces_back_to_both_feeders() {
+		let mut g = grid();
+		g.set_node(0, 1, Node::In(Ingredient::Hot)).unwrap();
+		g.set_node(1, 1, pipe(Left, Down)).unwrap();
+		g.set_node(0, 3, Node::In(Ingredient::Cold)).unwrap();
+		g.set_node(1, 3, pipe(Left, Up)).unwrap();
+		g.set_node(1, 2, Node::Merge(Ingredient::None, false, Right)).unwrap();
+		g.set_node(2, 1, Node::Generator).unwrap();
+		g.set_node(2, 2, Node::Out(Ingredient::None, 0, 0)).unwrap();
+		let upstream = g.trace_from(2, 2, false);
+		assert!(upstream.contains(&(0, 1)) && upstream.contains(&(0, 3)));
+		assert!(matches!(g.get_node(2, 2), Some(Node::Out(Ingredient::Hot, _, _))));
+	}
+	
+	#[test]
+	fn output_past_a_filter_keeps_its_count_through_edits() {
+		let mut g = grid();
+		g.set_node(0, 1, Node::In(Ingredient::Metal)).unwrap();
+		g.set_node(1, 1, pipe(Left, Down)).unwrap();
+		g.set_node(0, 3, Node::In(Ingredient::Hot)).unwrap();
+		g.set_node(1, 3, pipe(Left, Up)).unwrap();
+		g.set_node(1, 2, Node::Merge(Ingredient::None, false, Right)).unwrap();
+		g.set_node(2, 1, Node::Generator).unwrap();
+		g.set_node(2, 2, pipe(Left, Right)).unwrap();
+		g.set_node(3, 2, Node::Filter(Ingredient::None, Ingredient::Hot, Right)).unwrap();
+		g.set_node(4, 2, Node::Out(Ingredient::None, 0, 0)).unwrap();
+		g.set_node(3, 3, pipe(Up, Right)).unwrap();
+		g.set_node(4, 3, Node::Out(Ingredient::None, 0, 0)).unwrap();
+		run(&mut g, 20 * TICKS_PER_SECOND);
+		let before = match g.get_node(4, 2) {
+			Some(Node::Out(Ingredient::Hot, n, _)) if *n > 0 => *n,
+			_ => panic!("Expected Hot at the filter's straight output")
+		};
+		g.set_node(5, 2, Node::Generator).unwrap();
+		assert!(matches!(g.get_node(4, 2), Some(Node::Out(Ingredient::Hot, n, _)) if *n == before));
+	}
+	
+	#[test]
+	fn mixer_takes_two_inputs_through_its_back() {
+		let mut g = grid();
+		g.set_node(3, 3, Node::Machine(Machine::new("Mixer", 0, Right))).unwrap();
+		assert!(matches!(g.get_node(2, 3), Some(Node::Back("Mixer", Right))));
+		g.set_node(1, 2, Node::In(Ingredient::Pink)).unwrap();
+		g.set_node(2, 2, pipe(Left, Down)).unwrap();
+		g.set_node(1, 4, Node::In(Ingredient::Cold)).unwrap();
+		g.set_node(2, 4, pipe(Left, Up)).unwrap();
+		g.set_node(2, 1, Node::In(Ingredient::Milk)).unwrap();
+		g.set_node(3, 1, pipe(Left, Down)).unwrap();
+		g.set_node(3, 2, pipe(Up, Down)).unwrap();
+		g.set_node(2, 5, Node::In(Ingredient::Water)).unwrap();
+		g.set_node(3, 5, pipe(Left, Up)).unwrap();
+		g.set_node(3, 4, pipe(Down, Up)).unwrap();
+		g.set_node(4, 2, Node::Generator).unwrap();
+		g.set_node(4, 3, Node::Out(Ingredient::None, 0, 0)).unwrap();
+		assert!(g.sources(3, 3).contains(&(2, 2)) && g.sources(3, 3).contains(&(2, 4)));
+		run(&mut g, 30 * TICKS_PER_SECOND);
+		match g.get_node(4, 3) {
+			Some(Node::Out(Ingredient::Vodka, n, _)) => assert!(*n > 0),
+			_ => panic!("Expected Vodka at the output")
+		}
+	}
+	
+	#[test]
+	fn two_cell_machine_needs_and_clears_its_back() {
+		let mut g = grid();
+		g.set_node(0, 3, Node::Machine(Machine::new("Mixer", 0, Right))).unwrap_err();
+		g.set_node(2, 3, pipe(Left, Right)).unwrap();
+		g.set_node(3, 3, Node::Machine(Machine::new("Mixer", 0, Right))).unwrap_err();
+		assert!(g.get_node(3, 3).is_none());
+		g.set_node(4, 3, Node::Machine(Machine::new("Mixer", 0, Up))).unwrap();
+		assert!(matches!(g.get_node(4, 4), Some(Node::Back("Mixer", Up))));
+		g.remove_node(4, 4);
+		assert!(g.get_node(4, 3).is_none());
+	}
+}
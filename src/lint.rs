@@ -0,0 +1,83 @@
+use crate::{Ingredient, Node};
+use crate::grid::Grid;
+use crate::power;
+
+/// Problems shown on the grid all the time: machines whose inputs can't make anything, nodes fed
+/// two different ingredients, lines carrying items they aren't meant to, and machines without
+/// enough power
+pub fn diagnostics(grid: &Grid) -> Vec<(usize, usize, String)> {
+	let mut problems = vec![];
+	for (x, y, stray) in grid.mixed() {
+		problems.push((*x, *y, format!("Mixed line. {:?} got into it", stray)));
+	}
+	for (x, y, node) in grid.nodes() {
+		if let Some(problem) = node.resipee_problem(grid.resipees().clone()) {
+			problems.push((x, y, problem));
+		}
+		if node.power_draw() > 0 {
+			match power::network_at(grid, x, y) {
+				None => problems.push((x, y, format!("{} is unpowered", node.name()))),
+				Some(n) if !n.supplied() => problems.push((x, y, format!("{} is stalled. Its power network draws {} but only supplies {}", node.name(), n.draw, n.supply))),
+				Some(_) => {}
+			}
+		}
+		// Machines take a different ingredient on each side, and a crossing carries two flows that
+		// never meet, so only flows that would end up in the same place can conflict
+		if matches!(node, Node::Machine(_) | Node::Back(_, _)) {
+			continue;
+		}
+		for lane in [true, false] {
+			let mut feeds: Vec<Ingredient> = vec![];
+			for (fx, fy) in grid.feeders(x, y) {
+				if matches!(node, Node::Cross(_, _, _, _)) && (fy == y) != lane {
+					continue;
+				}
+				let ing = grid.flow((fx, fy), (x, y));
+				if ing != Ingredient::None && !feeds.contains(&ing) {
+					feeds.push(ing);
+				}
+			}
+			if feeds.len() > 1 {
+				problems.push((x, y, format!("Fed conflicting ingredients: {}", feeds.iter().map(|i| format!("{:?}", i)).collect::<Vec<String>>().join(", "))));
+			}
+			if !matches!(node, Node::Cross(_, _, _, _)) {
+				break;
+			}
+		}
+	}
+	problems
+}
+
+/// Everything `diagnostics` finds along with the rest of the problems with the layout: pipes
+/// going nowhere, machines not making anything yet and outputs with nothing coming in
+pub fn lint(grid: &Grid) -> Vec<(usize, usize, String)> {
+	let mut problems = diagnostics(grid);
+	for (x, y, node) in grid.nodes() {
+		match node {
+			Node::Pipe(_, _, _) | Node::Tee(_, _) | Node::Cross(_, _, _, _) | Node::Filter(_, _, _) => {
+				if grid.sources(x, y).is_empty() {
+					problems.push((x, y, format!("{} has nothing feeding it", node.name())));
+				}
+				for (tx, ty) in grid.outputs(x, y) {
+					let (ox, oy) = grid.owner(tx, ty);
+					match grid.get_node(tx, ty) {
+						Some(n) => if !grid.sources(ox, oy).contains(&(x, y)) {
+							problems.push((x, y, format!("{} points into a {} that doesn't take from it", node.name(), n.name())));
+						}
+						None => problems.push((x, y, format!("{} points into nothing", node.name())))
+					}
+				}
+				if grid.outputs(x, y).len() < node.outputs().len() {
+					problems.push((x, y, format!("{} points off the edge of the grid", node.name())));
+				}
+			}
+			Node::Machine(_) if node.get_ingredient(grid.resipees().clone()) == Ingredient::None
+				&& node.resipee_problem(grid.resipees().clone()).is_none() => {
+				problems.push((x, y, format!("{} isn't making anything. {}", node.name(), node.resipee_status(grid.resipees().clone()).unwrap_or_default())));
+			}
+			Node::Out(Ingredient::None, _, _) => problems.push((x, y, String::from("Output isn't receiving anything"))),
+			_ => {}
+		}
+	}
+	problems
+}
@@ -0,0 +1,108 @@
+use crate::direction::Direction;
+use crate::Ingredient;
+use crate::resipee::{Resipee, Stack};
+
+/// Most inputs a machine can have. A cell only has four sides and a machine needs one to output on,
+/// so machines taking more are two cells long
+pub const MAX_INPUTS: usize = 4;
+/// Most outputs a machine can have, leaving it a side to take inputs from
+pub const MAX_OUTPUTS: usize = 3;
+
+/// A machine placed on the grid
+#[derive(Copy, Clone)]
+pub struct Machine {
+	/// Name of its kind in `machine_list`
+	pub name: &'static str,
+	/// What it's being fed and how many of each it's holding, in the order of `Node::inputs`
+	pub inputs: [Stack; MAX_INPUTS],
+	/// What it's made and not sent on yet, in the order of `Node::outputs`
+	pub output: [Stack; MAX_OUTPUTS],
+	/// Counting from 0
+	pub level: u8,
+	pub powered: bool,
+	/// Machines facing right are laid out as their spec has them, anything else is that layout turned
+	pub facing: Direction,
+	/// How far through making its resipee it is, out of the resipee's `time`
+	pub progress: u32,
+}
+
+impl Machine {
+	pub fn new(name: &'static str, level: u8, facing: Direction) -> Machine {
+		Machine { name, inputs: [Stack::empty(); MAX_INPUTS], output: [Stack::empty(); MAX_OUTPUTS], level, powered: false, facing, progress: 0 }
+	}
+	
+	/// The ingredients it's being fed, whether or not any have arrived yet
+	pub fn fed(&self) -> [Ingredient; MAX_INPUTS] {
+		self.inputs.map(|s| s.ingredient)
+	}
+	
+	/// Items each of its inputs and outputs can hold
+	pub fn capacity(&self) -> u32 {
+		spec(self.name).map_or(0, |s| s.buffer)
+	}
+	
+	/// Whether one of everything it's fed has arrived
+	pub fn stocked(&self) -> bool {
+		self.inputs.iter().all(|s| s.ingredient == Ingredient::None || s.count > 0)
+	}
+	
+	/// Whether its outputs have space for another lot of what `r` makes
+	pub fn room_for(&self, r: &Resipee) -> bool {
+		r.outputs().iter().zip(self.output).all(|(ing, s)| s.count == 0 || (s.ingredient == *ing && s.count < self.capacity()))
+	}
+}
+
+/// A kind of machine: how it's laid out, drawn and upgraded
+#[derive(Copy, Clone, Debug)]
+pub struct MachineSpec {
+	pub name: &'static str,
+	/// What goes after ':p;' to place one, as in ':p;c1(1)'
+	pub code: &'static str,
+	/// Sides it takes ingredients in from when facing right, in the order they're shown
+	pub inputs: &'static [Direction],
+	/// Sides of the cell behind it that it takes ingredients in from when facing right, after
+	/// `inputs`. Machines with any take up that cell as well as their own
+	pub back: &'static [Direction],
+	/// Sides it sends its output out of when facing right. The first takes the main output and the
+	/// rest take byproducts in order
+	pub outputs: &'static [Direction],
+	/// How many levels there are. Nodes count levels from 0, so the top one is `levels - 1`
+	pub levels: u8,
+	/// Power drawn at level 1. Each level on top draws as much again
+	pub draw: u32,
+	/// Items each input and output holds before it stops taking more
+	pub buffer: u32,
+	/// Glyph in the custom font for each level that has one, facing right
+	pub glyphs: &'static [char],
+	/// Drawn before the arrow for its facing when there's no font glyph
+	pub letter: char,
+	/// Multiplied into the keys of the resipees it makes. Ingredients take the primes from 13 up
+	pub prime: u32,
+}
+
+/// Every kind of machine that can be placed
+pub fn machine_list() -> Vec<MachineSpec> {
+	vec![
+		MachineSpec { name: "Comb1", code: "c1", inputs: &[Direction::Up, Direction::Left, Direction::Down], back: &[], outputs: &[Direction::Right],
+			levels: 4, draw: 2, buffer: 4, glyphs: &['ᄗ', 'ᄘ'], letter: '1', prime: 2 },
+		MachineSpec { name: "Comb2", code: "c2", inputs: &[Direction::Up, Direction::Left, Direction::Down], back: &[], outputs: &[Direction::Right],
+			levels: 3, draw: 2, buffer: 4, glyphs: &['ᄙ', 'ᄚ'], letter: '2', prime: 3 },
+		// Presses sideways, so byproducts come out of the back
+		MachineSpec { name: "Press", code: "pr", inputs: &[Direction::Up, Direction::Down], back: &[], outputs: &[Direction::Right, Direction::Left],
+			levels: 3, draw: 1, buffer: 6, glyphs: &[], letter: 'P', prime: 5 },
+		// Two cells long, taking in from above and below both and sending its output out of the front
+		MachineSpec { name: "Mixer", code: "mx", inputs: &[Direction::Up, Direction::Down], back: &[Direction::Up, Direction::Down], outputs: &[Direction::Right],
+			levels: 2, draw: 3, buffer: 8, glyphs: &[], letter: 'X', prime: 7 },
+	]
+}
+
+impl MachineSpec {
+	/// How many ingredients it takes in across both its cells
+	pub fn input_count(&self) -> usize {
+		self.inputs.len() + self.back.len()
+	}
+}
+
+pub fn spec(name: &str) -> Option<MachineSpec> {
+	machine_list().into_iter().find(|m| m.name == name)
+}
@@ -1,9 +1,18 @@
 mod nodes;
 mod grid;
 mod resipee;
+mod console;
+mod book;
+mod dot;
+mod validate;
+mod lint;
+mod direction;
+mod glyphs;
+mod power;
+mod machines;
 
 use std::io::stdout;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use crossterm::{execute, Result, terminal::{SetSize, size}, cursor::{Hide, DisableBlinking}};
 use crossterm::event::{Event, read, poll, KeyCode};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen};
@@ -11,32 +20,63 @@ use crate::nodes::Node;
 use crate::resipee::Ingredient;
 
 fn main() -> Result<()> {
+	let args: Vec<String> = std::env::args().collect();
+	// `export-recipes <file>` writes the resipee graph out without starting the game
+	if args.get(1).map(|a| a.as_str()) == Some("export-recipes") {
+		match args.get(2) {
+			Some(path) => if let Err(e) = std::fs::write(path, dot::resipees_to_dot(&resipee::generate_resipees())) {
+				eprintln!("Cannot write '{}': {}", path, e);
+				std::process::exit(1);
+			}
+			None => {
+				eprintln!("Expected a file path after 'export-recipes'");
+				std::process::exit(1);
+			}
+		}
+		return Ok(());
+	}
 	execute!(stdout(), EnterAlternateScreen, Hide, DisableBlinking)?;
 	let (cols, rows) = size()?;
     // Resize terminal and scroll up.
 	enable_raw_mode()?;
 	let mut grid = grid::Grid::new(15, 15, resipee::generate_resipees());
+	// `--log <file>` mirrors the console to a session log for looking back over once the game has closed
+	if let Some(i) = args.iter().position(|a| a == "--log") {
+		match args.get(i + 1) {
+			Some(path) => if let Err(e) = grid.console.open_log(path) {
+				grid.console.push(console::Level::Error, format!("Cannot open session log '{}': {}", path, e));
+			}
+			None => grid.console.push(console::Level::Error, "Expected a file path after '--log'".to_string())
+		}
+	}
+	for issue in validate::validate(&resipee::resipee_list()) {
+		grid.console.push(console::Level::Warn, issue);
+	}
 	let mut command = String::new();
+	let tick = Duration::from_millis(1000 / grid::TICKS_PER_SECOND as u64);
+	let mut last_tick = Instant::now();
 	
 	loop {
-		if poll(Duration::from_millis(500))? {
-			match read()? {
-				Event::Key(e) => {
-					match e.code {
-						KeyCode::Char(c) => command.push(c),
-						KeyCode::Backspace => match command.pop() {
-							_ => {}
-						},
-						KeyCode::Enter => {
-							grid.execute_command(command.clone());
-							command.clear()
-						},
-						_ => {}
+		if poll(tick)? {
+			if let Event::Key(e) = read()? {
+				match e.code {
+					KeyCode::Char(c) => command.push(c),
+					KeyCode::Backspace => {
+						command.pop();
 					}
-				},
-				_ => ()
+					KeyCode::Enter => {
+						grid.execute_command(command.clone());
+						command.clear()
+					},
+					k => { grid.move_cursor(k); }
+				}
 			}
 		}
+		// Catch up on any ticks missed while waiting on a key or a command
+		while last_tick.elapsed() >= tick {
+			grid.tick();
+			last_tick += tick;
+		}
 		disable_raw_mode()?;
 		grid.print_to_stdout(command.clone())?;
 		enable_raw_mode()?;
@@ -3,19 +3,43 @@ use std::fmt::Formatter;
 
 use crossterm::style::Color;
 
+use crate::direction::Direction;
+use crate::glyphs::GlyphSet;
+use crate::machines::{self, Machine, MachineSpec};
 use crate::resipee::{Ingredient, Resipee, resipee_hash};
 
+/// Power a generator puts into its network
+pub const GENERATOR_SUPPLY: u32 = 4;
+
 #[derive(Copy, Clone)]
 pub enum Node {
 	In(Ingredient),
-	Out(Ingredient),
+	/// What's arriving, how many items have arrived and the tick counting started on
+	Out(Ingredient, u32, u64),
+	/// Power nodes feeding only the side they point to, `Left` for `PowerRight` and the other way round
 	PowerRight,
 	PowerLeft,
-	Comb1(Ingredient, Ingredient, Ingredient, u8),
-	Comb2(Ingredient, Ingredient, Ingredient, u8),
-	Split(Ingredient, bool),
-	Merge(Ingredient, bool),
-	Pipe(Ingredient, u8),
+	/// Puts `GENERATOR_SUPPLY` into the power network it's part of
+	Generator,
+	/// Joins power nodes into one network, feeding machines on every side
+	PowerLine,
+	Machine(Machine),
+	/// The cell behind a machine two cells long. The machine's name and the way it's facing, so the
+	/// machine is the next cell that way
+	Back(&'static str, Direction),
+	Split(Ingredient, bool, Direction),
+	Merge(Ingredient, bool, Direction),
+	/// Carried ingredient, the side it comes in from and the side it goes out of
+	Pipe(Ingredient, Direction, Direction),
+	/// Pipe splitting in two. Carried ingredient and the side it comes in from, leaving by the two
+	/// sides either side of that
+	Tee(Ingredient, Direction),
+	/// Two pipes crossing without mixing. The horizontal flow and the way it's going, then the
+	/// vertical flow and the way it's going
+	Cross(Ingredient, Direction, Ingredient, Direction),
+	/// Carried ingredient, the ingredient it lets straight through and the way that goes. Anything
+	/// else leaves by the side clockwise of that
+	Filter(Ingredient, Ingredient, Direction),
 }
 
 impl PartialEq for Node {
@@ -25,100 +49,312 @@ impl PartialEq for Node {
 }
 
 impl Node {
-	pub fn char(&self) -> char {
+	/// Glyph in the custom font, if the font has one for this node
+	pub fn char(&self) -> Option<char> {
 		match self {
-			Node::In(_) => 'ᄓ',
-			Node::Out(_) => 'ᄔ',
-			Node::PowerRight => 'ᄕ',
-			Node::PowerLeft => 'ᄖ',
-			Node::Comb1(_, _, _, level) => match level {
-				0 => 'ᄗ',
-				1 => 'ᄘ',
-				_ => unreachable!()
-			}
-			Node::Comb2(_, _, _, level) => match level {
-				0 => 'ᄙ',
-				1 => 'ᄚ',
-				_ => unreachable!()
-			}
-			Node::Split(_, _) => 'ᄛ',
-			Node::Merge(_, _) => 'ᄜ',
-			Node::Pipe(_, style) => match *style {
-				0 => 'ᄝ',
-				1 => 'ᄞ',
-				2 => 'ᄟ',
-				3 => 'ᄠ',
-				4 => 'ᄡ',
-				_ => unreachable!()
+			Node::In(_) => Some('ᄓ'),
+			Node::Out(_, _, _) => Some('ᄔ'),
+			Node::PowerRight => Some('ᄕ'),
+			Node::PowerLeft => Some('ᄖ'),
+			Node::Machine(m) if m.facing == Direction::Right => self.spec().and_then(|s| s.glyphs.get(m.level as usize).copied()),
+			Node::Split(_, _, Direction::Right) => Some('ᄛ'),
+			Node::Merge(_, _, Direction::Right) => Some('ᄜ'),
+			Node::Pipe(_, from, to) => match (from, to) {
+				(Direction::Left, Direction::Right) => Some('ᄝ'),
+				(Direction::Left, Direction::Up) => Some('ᄞ'),
+				(Direction::Left, Direction::Down) => Some('ᄟ'),
+				(Direction::Down, Direction::Right) => Some('ᄠ'),
+				(Direction::Up, Direction::Right) => Some('ᄡ'),
+				_ => None
 			}
+			_ => None
+		}
+	}
+	
+	/// The node turned a quarter turn, clockwise or not. Inputs, outputs and power nodes can't be turned
+	pub fn rotated(&self, clockwise: bool) -> Option<Node> {
+		let turn = |d: &Direction| if clockwise { d.clockwise() } else { d.clockwise().opposite() };
+		Some(match *self {
+			Node::Machine(m) => Node::Machine(Machine { facing: turn(&m.facing), ..m }),
+			Node::Split(i, p, f) => Node::Split(i, p, turn(&f)),
+			Node::Merge(i, p, f) => Node::Merge(i, p, turn(&f)),
+			Node::Pipe(i, from, to) => Node::Pipe(i, turn(&from), turn(&to)),
+			Node::Tee(i, from) => Node::Tee(i, turn(&from)),
+			// A quarter turn swaps which flow is the horizontal one
+			Node::Cross(h, hd, v, vd) => Node::Cross(v, turn(&vd), h, turn(&hd)),
+			Node::Filter(i, pass, f) => Node::Filter(i, pass, turn(&f)),
+			// Turned along with its machine
+			Node::Back(_, _) => return None,
+			Node::In(_) | Node::Out(_, _, _) | Node::PowerRight | Node::PowerLeft | Node::Generator | Node::PowerLine => return None
+		})
+	}
+	
+	/// Sides the node takes ingredients in from, in the order it uses them
+	pub fn inputs(&self) -> Vec<Direction> {
+		match self {
+			Node::Out(_, _, _) => vec![Direction::Left],
+			Node::Split(_, _, f) | Node::Filter(_, _, f) => vec![Direction::Left.turn(*f)],
+			Node::Machine(m) => self.spec().map_or(vec![], |s| s.inputs.iter().map(|d| d.turn(m.facing)).collect()),
+			Node::Merge(_, _, f) => vec![Direction::Up.turn(*f), Direction::Down.turn(*f)],
+			Node::Pipe(_, from, _) | Node::Tee(_, from) => vec![*from],
+			Node::Cross(_, h, _, v) => vec![h.opposite(), v.opposite()],
+			Node::In(_) | Node::PowerRight | Node::PowerLeft | Node::Generator | Node::PowerLine | Node::Back(_, _) => vec![]
+		}
+	}
+	
+	/// Sides of the cell behind a machine that it takes ingredients in from, after `inputs`
+	pub fn back_inputs(&self) -> Vec<Direction> {
+		match self {
+			Node::Machine(m) => self.spec().map_or(vec![], |s| s.back.iter().map(|d| d.turn(m.facing)).collect()),
+			_ => vec![]
+		}
+	}
+	
+	/// Sides the node sends its ingredient out of
+	pub fn outputs(&self) -> Vec<Direction> {
+		match self {
+			Node::In(_) => vec![Direction::Right],
+			Node::Machine(m) => self.spec().map_or(vec![], |s| s.outputs.iter().map(|d| d.turn(m.facing)).collect()),
+			Node::Merge(_, _, f) => vec![*f],
+			Node::Split(_, _, f) => vec![Direction::Up.turn(*f), Direction::Down.turn(*f)],
+			Node::Pipe(_, _, to) => vec![*to],
+			Node::Tee(_, from) => vec![from.clockwise(), from.clockwise().opposite()],
+			Node::Cross(_, h, _, v) => vec![*h, *v],
+			Node::Filter(_, _, f) => vec![*f, f.clockwise()],
+			Node::Out(_, _, _) | Node::PowerRight | Node::PowerLeft | Node::Generator | Node::PowerLine | Node::Back(_, _) => vec![]
+		}
+	}
+	
+	/// Whether items travel along the node rather than stopping in it
+	pub fn carries(&self) -> bool {
+		matches!(self, Node::Split(_, _, _) | Node::Merge(_, _, _) | Node::Pipe(_, _, _) | Node::Tee(_, _) | Node::Cross(_, _, _, _)
+			| Node::Filter(_, _, _))
+	}
+	
+	/// Whether the node is part of a power network, joining up with power nodes on every side
+	pub fn conducts(&self) -> bool {
+		matches!(self, Node::PowerRight | Node::PowerLeft | Node::Generator | Node::PowerLine)
+	}
+	
+	/// Whether a power node feeds a machine on `side` of it
+	pub fn powers_side(&self, side: Direction) -> bool {
+		match self {
+			Node::PowerRight => side == Direction::Left,
+			Node::PowerLeft => side == Direction::Right,
+			Node::Generator | Node::PowerLine => true,
+			_ => false
+		}
+	}
+	
+	/// Power the node needs from its network to run. Machines need more the higher their level
+	pub fn power_draw(&self) -> u32 {
+		match self {
+			Node::Machine(m) => self.spec().map_or(0, |s| s.draw * (m.level as u32 + 1)),
+			Node::Split(_, _, _) | Node::Merge(_, _, _) => 1,
+			_ => 0
+		}
+	}
+	
+	/// Level of a machine that has levels, counting from 0
+	pub fn level(&self) -> Option<u8> {
+		match self {
+			Node::Machine(m) => Some(m.level),
+			_ => None
+		}
+	}
+	
+	/// The same machine at `level`, keeping what it's fed and which way it faces
+	pub fn with_level(&self, level: u8) -> Node {
+		match *self {
+			Node::Machine(m) => Node::Machine(Machine { level, ..m }),
+			n => n
+		}
+	}
+	
+	/// How many times faster than a level 1 machine this one works
+	pub fn speed(&self) -> u32 {
+		self.level().map_or(1, |l| l as u32 + 1)
+	}
+	
+	/// What kind of machine this is, for machine nodes
+	pub fn spec(&self) -> Option<MachineSpec> {
+		match self {
+			Node::Machine(m) => machines::spec(m.name),
+			_ => None
+		}
+	}
+	
+	pub fn power_supply(&self) -> u32 {
+		match self {
+			Node::Generator => GENERATOR_SUPPLY,
+			_ => 0
+		}
+	}
+	
+	pub fn name(&self) -> &'static str {
+		match self {
+			Node::In(_) => "Input",
+			Node::Out(_, _, _) => "Output",
+			Node::PowerRight => "PowerRight",
+			Node::PowerLeft => "PowerLeft",
+			Node::Generator => "Generator",
+			Node::PowerLine => "PowerLine",
+			Node::Machine(m) => m.name,
+			Node::Back(name, _) => name,
+			Node::Split(_, _, _) => "Split",
+			Node::Merge(_, _, _) => "Merge",
+			Node::Pipe(_, _, _) => "Pipe",
+			Node::Tee(_, _) => "Tee",
+			Node::Cross(_, _, _, _) => "Crossing",
+			Node::Filter(_, _, _) => "Filter"
 		}
 	}
 	
 	pub fn info(&self, resipees: HashMap<u32, Resipee>) -> String {
 		match self {
 			Node::In(i) => format!("Input node. Input: {:?}", i),
-			Node::Out(i) => format!("Output node. Output: {:?}", i),
+			Node::Out(i, n, _) => format!("Output node. Output: {:?}, received: {}", i, n),
 			Node::PowerRight => String::from("Power right node"),
 			Node::PowerLeft => String::from("Power left node"),
-			Node::Comb1(i1, i2, i3, level) | Node::Comb2(i1, i2, i3, level) =>
-				format!("Comb{} node. In top: {:?}, in left: {:?}, in bottom: {:?}, level: {}, output: {:?}",
-						match self {
-							Node::Comb1(_, _, _, _) => 1,
-							_ => 2
-						}, i1, i2, i3, level, match resipees.get(&resipee_hash(self, &[*i1, *i2, *i3])) {
-						Some(r) => {
-							if *level >= r.machine.min_level {
-								r.output
-							} else {
-								Ingredient::None
-							}
-						}
-						None => Ingredient::None
-					}),
-			Node::Split(i, p) => format!("Split node ({}powered). Input: {:?}", if *p { "un" } else { "" }, i),
-			Node::Merge(i, p) => format!("Merge node ({}powered). Output: {:?}", if *p { "un" } else { "" }, i),
-			Node::Pipe(i, style) => format!("Pipe node. Carrying: {:?}, type: {}({})", i, match *style {
-				0 => "lr",
-				1 => "lu",
-				2 => "ld",
-				3 => "dr",
-				4 => "ur",
-				_ => unreachable!()
-			}, style)
+			Node::Generator => format!("Generator node. Supplies: {}", GENERATOR_SUPPLY),
+			Node::PowerLine => String::from("Power line node"),
+			Node::Machine(m) =>
+				format!("{} node ({}powered, draws {}) facing {}. {}, level: {} of {}, speed: x{}, holds: {}, outputs: {}",
+						m.name, if m.powered { "" } else { "un" }, self.power_draw(), m.facing.letter(),
+						self.inputs().iter().chain(&self.back_inputs()).zip(m.inputs).map(|(d, s)| format!("in {}: {:?}", d.letter(), s)).collect::<Vec<String>>().join(", "),
+						m.level + 1, self.spec().map_or(m.level + 1, |s| s.levels), self.speed(), m.capacity(), match self.products(resipees) {
+							products if products.is_empty() => String::from("None"),
+							products => self.outputs().iter().zip(products).zip(m.output)
+								.map(|((d, i), s)| format!("{} {:?} x{}", d.letter(), i, if s.ingredient == i { s.count } else { 0 }))
+								.collect::<Vec<String>>().join(", ")
+						}),
+			Node::Back(name, f) => format!("Back of a {} facing {}", name, f.letter()),
+			Node::Split(i, p, f) => format!("Split node ({}powered) facing {}. Input: {:?}", if *p { "" } else { "un" }, f.letter(), i),
+			Node::Merge(i, p, f) => format!("Merge node ({}powered) facing {}. Output: {:?}", if *p { "" } else { "un" }, f.letter(), i),
+			Node::Pipe(i, from, to) => format!("Pipe node. Carrying: {:?}, type: {}{}", i, from.letter(), to.letter()),
+			Node::Tee(i, from) => format!("Tee node. Carrying: {:?}, in from: {}", i, from.letter()),
+			Node::Cross(h, hd, v, vd) => format!("Crossing node. Carrying: {:?} going {}, {:?} going {}", h, hd.letter(), v, vd.letter()),
+			Node::Filter(i, pass, f) => format!("Filter node facing {}. Carrying: {:?}, passing: {:?}, anything else out of: {}", f.letter(), i, pass, f.clockwise().letter())
+		}
+	}
+	
+	/// For machines, what the node is making or else the resipees its current inputs are part of
+	/// and what they're missing
+	pub fn resipee_status(&self, resipees: HashMap<u32, Resipee>) -> Option<String> {
+		let m = match self {
+			Node::Machine(m) => m,
+			_ => return None
+		};
+		let ings = m.fed();
+		if let Some(r) = resipees.get(&resipee_hash(self.name(), &ings)) {
+			return Some(if m.level < r.machine.min_level {
+				format!("{:?} needs level {}", r.output, r.machine.min_level + 1)
+			} else if !m.powered {
+				format!("{:?} needs power", r.output)
+			} else if !m.room_for(r) {
+				format!("{:?} is backed up", r.output)
+			} else if m.progress == 0 && !m.stocked() {
+				format!("{:?} is waiting on its inputs", r.output)
+			} else {
+				format!("Making {:?} ({}%)", r.output, self.progress(&resipees).unwrap_or(0))
+			});
+		}
+		let mut partial = resipees.values()
+			.filter(|r| r.made_by(self))
+			.filter_map(|r| r.missing(&ings).map(|m| format!("{:?} (missing {})", r.output, m.iter().map(|i| format!("{:?}", i)).collect::<Vec<String>>().join(", "))))
+			.collect::<Vec<String>>();
+		partial.sort();
+		Some(if partial.is_empty() {
+			String::from("Inputs don't match any resipee")
+		} else {
+			format!("Could make {}", partial.join("; "))
+		})
+	}
+	
+	/// For machines, why the inputs it has can't make anything. Inputs that are only part of a
+	/// resipee so far aren't a problem
+	pub fn resipee_problem(&self, resipees: HashMap<u32, Resipee>) -> Option<String> {
+		let (ings, level) = match self {
+			Node::Machine(m) => (m.fed(), m.level),
+			_ => return None
+		};
+		if ings.iter().all(|i| *i == Ingredient::None) {
+			return None;
+		}
+		match resipees.get(&resipee_hash(self.name(), &ings)) {
+			Some(r) if level < r.machine.min_level => Some(format!("{:?} needs level {}", r.output, r.machine.min_level + 1)),
+			Some(_) => None,
+			None => if resipees.values().any(|r| r.made_by(self) && r.missing(&ings).is_some()) {
+				None
+			} else {
+				Some(String::from("Inputs don't match any resipee"))
+			}
 		}
 	}
 	
 	pub fn get_ingredient(&self, resipees: HashMap<u32, Resipee>) -> Ingredient {
 		match self {
 			Node::In(i) => *i,
-			Node::Out(i) => *i,
-			Node::PowerRight => Ingredient::None,
-			Node::PowerLeft => Ingredient::None,
-			Node::Comb1(i0, i1, i2, l) | Node::Comb2(i0, i1, i2, l) => {
-				match resipees.get(&resipee_hash(self, &[*i0, *i1, *i2])) {
-					Some(r) => {
-						if *l >= r.machine.min_level {
-							r.output
-						} else {
-							Ingredient::None
-						}
-					}
-					None => Ingredient::None
-				}
+			Node::Out(i, _, _) => *i,
+			Node::PowerRight | Node::PowerLeft | Node::Generator | Node::PowerLine | Node::Back(_, _) => Ingredient::None,
+			Node::Machine(_) => self.products(resipees).first().copied().unwrap_or(Ingredient::None),
+			// Stalled machines don't pass anything on
+			Node::Split(i, p, _) | Node::Merge(i, p, _) => if *p { *i } else { Ingredient::None },
+			Node::Pipe(i, _, _) | Node::Tee(i, _) | Node::Filter(i, _, _) => *i,
+			Node::Cross(h, _, v, _) => if *h == Ingredient::None { *v } else { *h }
+		}
+	}
+	
+	/// The resipee a machine is working on, if it has power, the inputs for one and a high enough level
+	pub fn making(&self, resipees: &HashMap<u32, Resipee>) -> Option<Resipee> {
+		match self {
+			Node::Machine(m) => resipees.get(&resipee_hash(m.name, &m.fed()))
+				.filter(|r| m.powered && m.level >= r.machine.min_level)
+				.copied(),
+			_ => None
+		}
+	}
+	
+	/// Percent of the way through making its resipee a machine is
+	pub fn progress(&self, resipees: &HashMap<u32, Resipee>) -> Option<u32> {
+		match (self, self.making(resipees)) {
+			(Node::Machine(m), Some(r)) => Some(m.progress * 100 / r.time.max(1)),
+			_ => None
+		}
+	}
+	
+	/// Everything a machine is making, main output first then any byproducts. Empty when it isn't
+	/// making anything
+	pub fn products(&self, resipees: HashMap<u32, Resipee>) -> Vec<Ingredient> {
+		self.making(&resipees).map_or(vec![], |r| r.outputs())
+	}
+	
+	/// What the node sends out of `side`. Only differs from `get_ingredient` for crossings, which
+	/// carry two things at once, machines, which send byproducts out of their other output sides,
+	/// and filters, which send what they're carrying one way or the other
+	pub fn ingredient_to(&self, side: Direction, resipees: HashMap<u32, Resipee>) -> Ingredient {
+		match self {
+			Node::Cross(h, hd, v, _) => if side == *hd { *h } else { *v },
+			Node::Filter(i, pass, f) => if (side == *f) == (i == pass) { *i } else { Ingredient::None },
+			Node::Machine(_) => match self.outputs().iter().position(|d| *d == side) {
+				Some(n) => self.products(resipees).get(n).copied().unwrap_or(Ingredient::None),
+				None => Ingredient::None
 			}
-			Node::Split(i, _) => *i,
-			Node::Merge(i, _) => *i,
-			Node::Pipe(i, _) => *i
+			_ => self.get_ingredient(resipees)
 		}
 	}
 	
+	/// Colour the node is drawn in. Filters show what they let through rather than what they carry
 	pub fn col(&self, resipees: HashMap<u32, Resipee>) -> Color {
-		self.get_ingredient(resipees).get_colour()
+		match self {
+			Node::Filter(_, pass, _) => pass.get_colour(),
+			_ => self.get_ingredient(resipees).get_colour()
+		}
 	}
 }
 
 impl std::fmt::Display for Node {
 	fn fmt(&self, _f: &mut Formatter<'_>) -> std::fmt::Result {
-		write!(_f, "{}", self.char())
+		write!(_f, "{}", GlyphSet::Font.node(self))
 	}
 }
@@ -0,0 +1,57 @@
+use crate::direction::Position;
+use crate::grid::Grid;
+
+/// Power nodes joined up side by side, along with the machines they feed
+pub struct Network {
+	pub nodes: Vec<Position>,
+	pub machines: Vec<Position>,
+	pub supply: u32,
+	pub draw: u32,
+}
+
+impl Network {
+	/// Whether the generators keep up with every machine on the network. When they don't, all of
+	/// them stall rather than some running and some not
+	pub fn supplied(&self) -> bool {
+		self.draw <= self.supply
+	}
+	
+	pub fn describe(&self) -> String {
+		format!("Power network supplies {}, draws {} across {} machine{}{}", self.supply, self.draw, self.machines.len(),
+				if self.machines.len() == 1 { "" } else { "s" }, if self.supplied() { "" } else { " (stalled)" })
+	}
+}
+
+/// Every power network on the grid
+pub fn networks(grid: &Grid) -> Vec<Network> {
+	let mut networks: Vec<Network> = vec![];
+	for (x, y, node) in grid.nodes() {
+		if !node.conducts() || networks.iter().any(|n| n.nodes.contains(&(x, y))) {
+			continue;
+		}
+		let mut nodes = vec![(x, y)];
+		let mut next = 0;
+		while next < nodes.len() {
+			let (cx, cy) = nodes[next];
+			for pos in grid.neighbours(cx, cy) {
+				if !nodes.contains(&pos) && grid.get_node(pos.0, pos.1).is_some_and(|n| n.conducts()) {
+					nodes.push(pos);
+				}
+			}
+			next += 1;
+		}
+		let machines = grid.nodes()
+			.filter(|(mx, my, _)| grid.powered_by(*mx, *my).is_some_and(|p| nodes.contains(&p)))
+			.map(|(mx, my, _)| (mx, my))
+			.collect::<Vec<Position>>();
+		let supply = nodes.iter().filter_map(|(nx, ny)| grid.get_node(*nx, *ny)).map(|n| n.power_supply()).sum();
+		let draw = machines.iter().filter_map(|(mx, my)| grid.get_node(*mx, *my)).map(|n| n.power_draw()).sum();
+		networks.push(Network { nodes, machines, supply, draw });
+	}
+	networks
+}
+
+/// The network the node at `(x, y)` is part of or draws from
+pub fn network_at(grid: &Grid, x: usize, y: usize) -> Option<Network> {
+	networks(grid).into_iter().find(|n| n.nodes.contains(&(x, y)) || n.machines.contains(&(x, y)))
+}
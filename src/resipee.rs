@@ -6,57 +6,218 @@ use enum_iterator::IntoEnumIterator;
 use crate::Ingredient::*;
 
 use crate::Node;
-use crate::Node::{Comb1, Comb2};
+use crate::grid::TICKS_PER_SECOND;
+use crate::machines::{self, MAX_INPUTS};
 
 #[derive(Copy, Clone)]
 pub struct Resipee {
-	input: [Ingredient; 3],
+	pub input: [Ingredient; MAX_INPUTS],
 	pub machine: MachineRequirement,
 	pub output: Ingredient,
+	/// Made alongside `output`, each going out of the machine's next output side in turn
+	pub byproducts: &'static [Ingredient],
+	/// Inputs that have to be at the machine but aren't used up by making this
+	pub catalysts: &'static [Ingredient],
+	/// Ticks a level 1 machine takes to make this
+	pub time: u32,
+}
+
+impl Resipee {
+	pub fn key(&self) -> u32 {
+		resipee_hash(self.machine.machine, &self.input)
+	}
+	
+	/// Inputs used up each time this is made, leaving out catalysts
+	pub fn consumed(&self) -> Vec<Ingredient> {
+		self.input.iter().filter(|i| **i != None && !self.catalysts.contains(i)).copied().collect()
+	}
+	
+	/// `output` followed by the byproducts, in the order of the machine's output sides
+	pub fn outputs(&self) -> Vec<Ingredient> {
+		std::iter::once(self.output).chain(self.byproducts.iter().copied()).collect()
+	}
+	
+	pub fn made_by(&self, machine: &Node) -> bool {
+		self.machine.machine == machine.name()
+	}
+	
+	/// The inputs still needed to go with `ings` to make this resipee, or `None` if `ings` has
+	/// something this resipee doesn't use
+	pub fn missing(&self, ings: &[Ingredient]) -> Option<Vec<Ingredient>> {
+		let mut needed: Vec<Ingredient> = self.input.iter().filter(|i| **i != None).copied().collect();
+		for ing in ings.iter().filter(|i| **i != None) {
+			match needed.iter().position(|n| n == ing) {
+				Some(p) => { needed.remove(p); }
+				Option::None => return Option::None
+			}
+		}
+		Some(needed)
+	}
 }
 
 #[derive(Copy, Clone)]
 pub struct MachineRequirement {
-	machine: Node,
+	/// Name of the machine in `machines::machine_list`
+	machine: &'static str,
 	pub min_level: u8,
 }
 
+impl MachineRequirement {
+	pub fn name(&self) -> &'static str {
+		self.machine
+	}
+}
+
 impl Debug for MachineRequirement {
 	fn fmt(&self, f_: &mut Formatter<'_>) -> std::fmt::Result {
-		write!(f_, "machine: {}, min level: {}", match self.machine {
-			Comb1(_, _, _, _) => "Comb1",
-			Comb2(_, _, _, _) => "Comb2",
-			_ => unreachable!()
-		}, self.min_level + 1)
+		write!(f_, "machine: {}, min level: {}", self.name(), self.min_level + 1)
+	}
+}
+
+impl Debug for Resipee {
+	fn fmt(&self, f_: &mut Formatter<'_>) -> std::fmt::Result {
+		let inputs = self.input.iter().filter(|i| **i != None).map(|i| if self.catalysts.contains(i) {
+			format!("{:?} (catalyst)", i)
+		} else {
+			format!("{:?}", i)
+		}).collect::<Vec<String>>();
+		let seconds = self.time as f32 / TICKS_PER_SECOND as f32;
+		if self.byproducts.is_empty() {
+			write!(f_, "{} -> {:?} | {:?} | {}s", inputs.join(" + "), self.output, self.machine, seconds)
+		} else {
+			write!(f_, "{} -> {:?} (byproducts: {}) | {:?} | {}s", inputs.join(" + "), self.output,
+				self.byproducts.iter().map(|i| format!("{:?}", i)).collect::<Vec<String>>().join(", "), self.machine, seconds)
+		}
 	}
 }
 
-pub fn resipee_hash(machine: &Node, ings: &[Ingredient; 3]) -> u32 {
-	// 2 | 3 | 5 | 7 | 11
-	let mut out = match machine {
-		Comb1(_, _, _, _) => 2u32,
-		Comb2(_, _, _, _) => 3u32,
-		_ => unreachable!()
-	};
+/// Key of the resipee `machine` makes from `ings`, whatever order they're in
+pub fn resipee_hash(machine: &str, ings: &[Ingredient; MAX_INPUTS]) -> u32 {
+	// Machines take the primes below 13 so they can't be mistaken for an ingredient
+	let mut out = machines::spec(machine).map_or(1, |m| m.prime);
 	for ing in ings {
 		out *= ing.index()
 	}
 	out
 }
 
+/// Every resipee, before they're keyed by `resipee_hash`. Two resipees with the same key here
+/// means one silently replaces the other, so run `validate::validate` over this after changes
+pub fn resipee_list() -> Vec<Resipee> {
+	vec![
+		Resipee { input: [Hot, Water, Milk, None], machine: MachineRequirement { machine: "Comb1", min_level: 0}, output: Coffee, byproducts: &[], catalysts: &[], time: 8 },
+		Resipee { input: [Coffee, Cat, Pink, None], machine: MachineRequirement { machine: "Comb2", min_level: 0}, output: Metal, byproducts: &[], catalysts: &[Cat], time: 12 },
+		Resipee { input: [Metal, Hot, None, None], machine: MachineRequirement { machine: "Comb1", min_level: 1}, output: OtherMetal, byproducts: &[], catalysts: &[], time: 16 },
+		Resipee { input: [Pink, Cold, Milk, None], machine: MachineRequirement { machine: "Comb1", min_level: 1}, output: Vodka, byproducts: &[], catalysts: &[], time: 12 },
+		Resipee { input: [Metal, Hot, None, None], machine: MachineRequirement { machine: "Press", min_level: 0}, output: OtherMetal, byproducts: &[Water], catalysts: &[], time: 20 },
+		Resipee { input: [Pink, Cold, Milk, Water], machine: MachineRequirement { machine: "Mixer", min_level: 0}, output: Vodka, byproducts: &[], catalysts: &[], time: 8 },
+	]
+}
+
 pub fn generate_resipees() -> HashMap<u32, Resipee> {
 	let mut resipees: HashMap<u32, Resipee> = HashMap::new();
-	let r1 = Resipee { input: [Hot, Water, Milk], machine: MachineRequirement { machine: Comb1(None, None, None, 0), min_level: 0}, output: Coffee };
-	let r2 = Resipee { input: [Coffee, Cat, Pink], machine: MachineRequirement { machine: Comb2(None, None, None, 0), min_level: 0}, output: Metal };
-	let r3 = Resipee { input: [Metal, Hot, None], machine: MachineRequirement { machine: Comb1(None, None, None, 0), min_level: 1}, output: OtherMetal };
-	let r4 = Resipee { input: [Pink, Cold, Milk], machine: MachineRequirement { machine: Comb1(None, None, None, 0), min_level: 1}, output: Vodka };
-	for r in [r1, r2, r3, r4] {
-		resipees.insert(resipee_hash(&r.machine.machine, &r.input), r);
+	for r in resipee_list() {
+		resipees.insert(r.key(), r);
 	}
 	resipees
 }
 
-#[derive(Copy, Clone, IntoEnumIterator, Debug)]
+/// The resipee used to make `ing`. Where more than one makes it, the one needing the lowest level
+pub fn producer(ing: Ingredient, resipees: &HashMap<u32, Resipee>) -> Option<&Resipee> {
+	resipees.values()
+		.filter(|r| r.output == ing)
+		.min_by_key(|r| (r.machine.min_level, r.machine.name()))
+}
+
+/// Every resipee with `ing` as an input
+pub fn consumers(ing: Ingredient, resipees: &HashMap<u32, Resipee>) -> Vec<&Resipee> {
+	let mut out = resipees.values().filter(|r| r.input.contains(&ing)).collect::<Vec<&Resipee>>();
+	out.sort_by_key(|r| (r.machine.name(), r.machine.min_level, format!("{:?}", r.output)));
+	out
+}
+
+/// Every ingredient that can be made from `sources` using machines up to the given levels, in the
+/// order they become makeable
+pub fn reachable(sources: &[Ingredient], machines: &[(&str, u8)], resipees: &HashMap<u32, Resipee>) -> Vec<Ingredient> {
+	let mut have = sources.to_vec();
+	let mut made = vec![];
+	loop {
+		let next = resipees.values().find(|r| !have.contains(&r.output)
+			&& machines.iter().any(|(m, l)| *m == r.machine.name() && *l >= r.machine.min_level)
+			&& r.input.iter().all(|i| *i == None || have.contains(i)));
+		match next {
+			Some(r) => {
+				for out in r.outputs() {
+					if !have.contains(&out) {
+						have.push(out);
+						made.push(out);
+					}
+				}
+			}
+			Option::None => return made
+		}
+	}
+}
+
+/// Everything needed to make one of an ingredient, expanded down to base ingredients
+pub struct Chain {
+	/// Indented lines of the expanded tree
+	pub tree: Vec<String>,
+	pub raw: Vec<(Ingredient, u32)>,
+	/// Machine name, minimum level and number of that machine needed
+	pub machines: Vec<(&'static str, u8, u32)>,
+	/// Catalysts needed somewhere along the way. Only one of each is needed however much is made
+	pub catalysts: Vec<Ingredient>,
+	/// Most machines any one base ingredient passes through
+	pub depth: usize,
+}
+
+impl Chain {
+	pub fn new(ing: Ingredient, resipees: &HashMap<u32, Resipee>) -> Result<Chain, String> {
+		let mut chain = Chain { tree: vec![], raw: vec![], machines: vec![], catalysts: vec![], depth: 0 };
+		chain.expand(ing, resipees, &mut vec![])?;
+		Ok(chain)
+	}
+	
+	fn expand(&mut self, ing: Ingredient, resipees: &HashMap<u32, Resipee>, path: &mut Vec<Ingredient>) -> Result<(), String> {
+		if path.contains(&ing) {
+			path.push(ing);
+			return Err(format!("Resipee cycle: {}", path.iter().map(|i| format!("{:?}", i)).collect::<Vec<String>>().join(" -> ")));
+		}
+		let indent = "  ".repeat(path.len());
+		match producer(ing, resipees) {
+			Some(r) => {
+				self.tree.push(format!("{}{:?} ({:?})", indent, ing, r.machine));
+				self.depth = self.depth.max(path.len() + 1);
+				match self.machines.iter_mut().find(|(n, l, _)| *n == r.machine.name() && *l == r.machine.min_level) {
+					Some((_, _, count)) => *count += 1,
+					Option::None => self.machines.push((r.machine.name(), r.machine.min_level, 1))
+				}
+				path.push(ing);
+				for input in r.consumed() {
+					self.expand(input, resipees, path)?;
+				}
+				path.pop();
+				for catalyst in r.catalysts {
+					self.tree.push(format!("{}  {:?} (catalyst)", indent, catalyst));
+					if !self.catalysts.contains(catalyst) {
+						self.catalysts.push(*catalyst);
+					}
+				}
+			}
+			Option::None => {
+				self.tree.push(format!("{}{:?} (base)", indent, ing));
+				match self.raw.iter_mut().find(|(i, _)| *i == ing) {
+					Some((_, count)) => *count += 1,
+					Option::None => self.raw.push((ing, 1))
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+#[derive(Copy, Clone, IntoEnumIterator, Debug, PartialEq, Eq, Hash)]
 pub enum Ingredient {
 	None,
 	Hot,
@@ -120,30 +281,31 @@ impl Ingredient {
 		}
 	}
 	
-	pub fn resipee(&self) -> (Vec<Ingredient>, Option<MachineRequirement>) {
-		match self {
-			None | Pink | Hot | Cold | Cat | Milk | Water => (vec![self.clone()], Option::None),
-			Coffee => (vec![Hot, Water, Milk], Some(MachineRequirement { machine: Comb1(None, None, None, 0), min_level: 0})),
-			Metal => (vec![Coffee, Cat, Pink], Some(MachineRequirement { machine: Comb2(None, None, None, 0), min_level: 0})),
-			OtherMetal => (vec![Metal, Hot], Some(MachineRequirement { machine: Comb1(None, None, None, 0), min_level: 1})),
-			Vodka => (vec![Pink, Cold, Milk], Some(MachineRequirement { machine: Comb1(None, None, None, 0), min_level: 1}))
-		}
+	/// The ingredient shown as number `v` in the legend, if there is one
+	pub fn u16_to_ing(v: u16) -> Option<Ingredient> {
+		Ingredient::into_enum_iter().nth(v as usize)
 	}
 	
-	pub fn u16_to_ing(v: u16) -> Ingredient {
-		match v {
-			0 => None,
-			1 => Hot,
-			2 => Cold,
-			3 => Metal,
-			4 => Milk,
-			5 => OtherMetal,
-			6 => Water,
-			7 => Pink,
-			8 => Coffee,
-			9 => Vodka,
-			10 => Cat,
-			_ => unreachable!()
-		}
+	pub fn from_name(name: &str) -> Option<Ingredient> {
+		Ingredient::into_enum_iter().find(|i| format!("{:?}", i).eq_ignore_ascii_case(name))
+	}
+}
+
+/// A number of items of one ingredient, such as what a machine is holding on one side
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Stack {
+	pub ingredient: Ingredient,
+	pub count: u32,
+}
+
+impl Stack {
+	pub fn empty() -> Stack {
+		Stack { ingredient: None, count: 0 }
+	}
+}
+
+impl Debug for Stack {
+	fn fmt(&self, f_: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f_, "{:?} x{}", self.ingredient, self.count)
 	}
 }
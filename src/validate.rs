@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use enum_iterator::IntoEnumIterator;
+
+use crate::Ingredient;
+use crate::direction::Direction;
+use crate::machines;
+use crate::resipee::{self, Chain, Resipee};
+
+/// Problems with a resipee list: resipees sharing a key, ingredients that can't be made from
+/// base ingredients, catalysts that aren't inputs, machines that can't reach the level needed or
+/// take that many inputs, machine specs that clash or don't fit in their cells, and cycles
+pub fn validate(list: &[Resipee]) -> Vec<String> {
+	let mut issues = vec![];
+	let mut resipees: HashMap<u32, Resipee> = HashMap::new();
+	for r in list {
+		if let Some(other) = resipees.insert(r.key(), *r) {
+			issues.push(format!("Duplicate resipee key {}: '{:?}' replaces '{:?}'", r.key(), r, other));
+		}
+	}
+
+	// Anything no resipee makes has to come from an input node
+	let base = Ingredient::into_enum_iter().skip(1)
+		.filter(|i| !list.iter().any(|r| r.output == *i))
+		.collect::<Vec<Ingredient>>();
+	let mut machines: Vec<(&str, u8)> = list.iter().map(|r| (r.machine.name(), u8::MAX)).collect();
+	machines.dedup();
+	let made = resipee::reachable(&base, &machines, &resipees);
+	let mut unreachable = list.iter().map(|r| r.output).filter(|i| !made.contains(i)).collect::<Vec<Ingredient>>();
+	unreachable.sort_by_key(|i| i.index());
+	unreachable.dedup();
+	for ing in unreachable {
+		issues.push(format!("{:?} can't be made from base ingredients", ing));
+	}
+
+	for r in list {
+		match machines::spec(r.machine.name()) {
+			Some(spec) if r.machine.min_level >= spec.levels => issues.push(format!("'{:?}' needs a level {} {}, which only goes up to level {}", r, r.machine.min_level + 1, spec.name, spec.levels)),
+			Some(spec) if r.input.iter().filter(|i| **i != Ingredient::None).count() > spec.input_count() =>
+				issues.push(format!("'{:?}' has more inputs than a {} takes", r, spec.name)),
+			_ if r.catalysts.iter().any(|c| !r.input.contains(c)) => issues.push(format!("'{:?}' has a catalyst that isn't one of its inputs", r)),
+			Some(spec) if r.outputs().len() > spec.outputs.len() => issues.push(format!("'{:?}' has more outputs than a {} has sides for", r, spec.name)),
+			Some(_) => {}
+			None => issues.push(format!("'{:?}' needs a {}, which isn't in the machine list", r, r.machine.name()))
+		}
+	}
+
+	let specs = machines::machine_list();
+	for (n, spec) in specs.iter().enumerate() {
+		if let Some(other) = specs[..n].iter().find(|o| o.name == spec.name || o.code == spec.code || o.prime == spec.prime) {
+			issues.push(format!("Machines {} and {} share a name, code or prime", other.name, spec.name));
+		}
+		if spec.inputs.is_empty() || spec.input_count() > machines::MAX_INPUTS || spec.outputs.is_empty() || spec.outputs.len() > machines::MAX_OUTPUTS
+			|| spec.inputs.iter().any(|d| spec.outputs.contains(d)) {
+			issues.push(format!("{} needs 1 to {} input sides and 1 to {} other sides to output on", spec.name, machines::MAX_INPUTS, machines::MAX_OUTPUTS));
+		}
+		// The back joins on to the left when facing right
+		if !spec.back.is_empty() && (spec.inputs.contains(&Direction::Left) || spec.outputs.contains(&Direction::Left) || spec.back.contains(&Direction::Right)) {
+			issues.push(format!("{} is two cells long, so neither cell can use the side the other is on", spec.name));
+		}
+		if spec.buffer == 0 {
+			issues.push(format!("{} has no room to hold what it's fed", spec.name));
+		}
+		if spec.prime >= Ingredient::Hot.index() {
+			issues.push(format!("{}'s prime {} could be mistaken for an ingredient's", spec.name, spec.prime));
+		}
+	}
+
+	for cycle in cycles(list) {
+		issues.push(format!("Resipee cycle: {}", cycle.iter().map(|i| format!("{:?}", i)).collect::<Vec<String>>().join(" -> ")));
+	}
+	issues
+}
+
+/// Every loop of ingredients each made from the next, found by walking from outputs to inputs
+fn cycles(list: &[Resipee]) -> Vec<Vec<Ingredient>> {
+	fn walk(ing: Ingredient, list: &[Resipee], path: &mut Vec<Ingredient>, done: &mut Vec<Ingredient>, found: &mut Vec<Vec<Ingredient>>) {
+		if let Some(start) = path.iter().position(|i| *i == ing) {
+			let mut cycle = path[start..].to_vec();
+			cycle.push(ing);
+			found.push(cycle);
+			return;
+		}
+		if done.contains(&ing) {
+			return;
+		}
+		path.push(ing);
+		for r in list.iter().filter(|r| r.output == ing) {
+			for input in r.input.iter().filter(|i| **i != Ingredient::None) {
+				walk(*input, list, path, done, found);
+			}
+		}
+		path.pop();
+		done.push(ing);
+	}
+
+	let mut found = vec![];
+	let mut done = vec![];
+	for ing in Ingredient::into_enum_iter().skip(1) {
+		walk(ing, list, &mut vec![], &mut done, &mut found);
+	}
+	found
+}
+
+/// Raw ingredient cost and machine depth of everything the resipees make
+pub fn balance(resipees: &HashMap<u32, Resipee>) -> Vec<String> {
+	Ingredient::into_enum_iter().filter(|i| resipees.values().any(|r| r.output == *i)).map(|ing| match Chain::new(ing, resipees) {
+		Ok(chain) => format!("{:?}: {} raw ({}) | machine depth {} | {} machines",
+			ing,
+			chain.raw.iter().map(|(_, n)| n).sum::<u32>(),
+			chain.raw.iter().map(|(i, n)| format!("{} {:?}", n, i)).collect::<Vec<String>>().join(", "),
+			chain.depth,
+			chain.machines.iter().map(|(_, _, n)| n).sum::<u32>()),
+		Err(e) => format!("{:?}: {}", ing, e)
+	}).collect()
+}